@@ -4,7 +4,9 @@ MEMORY
   /* To suit Raspberry Pi RP2040 SoC */
   BOOT_LOADER : ORIGIN = 0x10000000, LENGTH = 0x100
   /* Adjust this to suit the size of your specific flash chip */
-  FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+  FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 64K
+  /* Reserved for the settings, must match `SETTINGS_OFFSET` and `SETTINGS_SIZE` in src/storage.rs */
+  SETTINGS : ORIGIN = 0x10000000 + 2048K - 64K, LENGTH = 64K
   RAM : ORIGIN = 0x20000000, LENGTH = 264K
 }
 
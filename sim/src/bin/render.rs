@@ -0,0 +1,202 @@
+//! Renders a show into an image.
+//!
+//! Runs the show for a number of simulated seconds and writes the frames either
+//! as an animated GIF or as a PNG strip, where every row is one frame.
+//! With `--check` the PNG strip is compared against an existing (golden) image instead.
+//! The colors are the ones sent to the strip, after the calibration and the power limit.
+
+use std::{fs::File, io::BufWriter, path::PathBuf, process::ExitCode};
+
+use arclib::{
+  control::{show_from_name, SHOW_NAMES},
+  geometry::Geometry,
+  nl,
+  output::OutputSettings,
+  strip::StripConfig,
+};
+use arcus_sim::{capture, check_png, Capture};
+
+const USAGE: &str = "\
+usage: render <show> <output.gif|output.png> [options]
+       render <show> --check <golden.png> [options]
+
+options:
+  --seconds <s>      simulated duration (default: 10)
+  --fps <n>          captured frames per second (default: 25)
+  --leds <n>         number of lights (default: 240)
+  --seed <n>         seed for random shows (default: 0)
+  --brightness <b>   brightness between 0 and 1 (default: 1)
+  --calibrate <s>    calibration setting like the shell command, e.g. \"gamma 2.2\",
+                     can be given several times
+  --power <s>        power limit setting like the shell command, e.g. \"budget off\",
+                     can be given several times
+  --geometry <g>     positions of the lights, e.g. \"ring 240\" or
+                     \"matrix 16 15 serpentine\" (default: linear)
+  --scale <n>        size of a light in pixels, GIF only (default: 4)";
+
+struct Options {
+  show: String,
+  output: PathBuf,
+  check: bool,
+  capture: Capture,
+  geometry: Option<String>,
+  scale: usize,
+}
+
+impl Options {
+  fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+    let mut show = None;
+    let mut output = None;
+    let mut check = false;
+    let mut seconds = 10;
+    let mut fps = 25;
+    let mut leds = StripConfig::default().nlights;
+    let mut seed = 0;
+    // without dithering, so the frames don't depend on the ones before
+    let mut settings = OutputSettings {
+      dithering: false,
+      ..OutputSettings::default()
+    };
+    let mut geometry = None;
+    let mut scale = 4;
+
+    fn value<T: std::str::FromStr>(
+      args: &mut impl Iterator<Item = String>,
+      name: &str,
+    ) -> Result<T, String> {
+      args
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("invalid or missing value for `{}`", name))
+    }
+
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--check" => {
+          check = true;
+          output = Some(value(&mut args, &arg)?);
+        }
+        "--seconds" => seconds = value(&mut args, &arg)?,
+        "--fps" => fps = value(&mut args, &arg)?,
+        "--leds" => leds = value(&mut args, &arg)?,
+        "--seed" => seed = value(&mut args, &arg)?,
+        "--brightness" => settings.brightness = nl!(value::<f32>(&mut args, &arg)?),
+        "--calibrate" => settings
+          .calibration
+          .update(&value::<String>(&mut args, &arg)?)
+          .map_err(|e| format!("invalid calibration: {}", e))?,
+        "--power" => settings
+          .power
+          .update(&value::<String>(&mut args, &arg)?)
+          .map_err(|e| format!("invalid power limit: {}", e))?,
+        "--geometry" => geometry = Some(value(&mut args, &arg)?),
+        "--scale" => scale = value(&mut args, &arg)?,
+        _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+        _ if show.is_none() => show = Some(arg),
+        _ if output.is_none() => output = Some(PathBuf::from(arg)),
+        _ => return Err(format!("unexpected argument `{}`", arg)),
+      }
+    }
+
+    if fps == 0 || leds == 0 || scale == 0 {
+      return Err("`--fps`, `--leds` and `--scale` must be positive".into());
+    }
+    Ok(Self {
+      show: show.ok_or("missing show")?,
+      output: output.ok_or("missing output")?,
+      check,
+      capture: Capture {
+        seconds,
+        fps,
+        leds,
+        seed,
+        output: settings,
+      },
+      geometry,
+      scale,
+    })
+  }
+}
+
+fn write_png(frames: &[Vec<[u8; 3]>], opts: &Options) -> Result<(), String> {
+  let file = File::create(&opts.output).map_err(|e| e.to_string())?;
+  let mut encoder = png::Encoder::new(
+    BufWriter::new(file),
+    opts.capture.leds as u32,
+    frames.len() as u32,
+  );
+  encoder.set_color(png::ColorType::Rgb);
+  encoder.set_depth(png::BitDepth::Eight);
+  let data: Vec<u8> = frames.iter().flatten().flatten().copied().collect();
+  encoder
+    .write_header()
+    .and_then(|mut writer| writer.write_image_data(&data))
+    .map_err(|e| e.to_string())
+}
+
+fn write_gif(frames: &[Vec<[u8; 3]>], opts: &Options) -> Result<(), String> {
+  let width = opts.capture.leds * opts.scale;
+  let height = opts.scale;
+
+  let file = File::create(&opts.output).map_err(|e| e.to_string())?;
+  let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+    .map_err(|e| e.to_string())?;
+  encoder
+    .set_repeat(gif::Repeat::Infinite)
+    .map_err(|e| e.to_string())?;
+
+  for frame in frames {
+    let row: Vec<u8> = frame
+      .iter()
+      .flat_map(|rgb| std::iter::repeat_n(rgb, opts.scale))
+      .flatten()
+      .copied()
+      .collect();
+    let pixels = row.repeat(height);
+    let mut frame = gif::Frame::from_rgb(width as u16, height as u16, &pixels);
+    // in units of 10ms
+    frame.delay = (100 / opts.capture.fps) as u16;
+    encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+  }
+  Ok(())
+}
+
+fn run() -> Result<(), String> {
+  let opts = Options::parse(std::env::args().skip(1)).map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+  let mut show = show_from_name(&opts.show).ok_or_else(|| {
+    format!(
+      "unknown show `{}`, available: {}",
+      opts.show,
+      SHOW_NAMES.join(", ")
+    )
+  })?;
+
+  let leds = opts.capture.leds;
+  let mut geometry = Geometry::linear(leds);
+  if let Some(setting) = &opts.geometry {
+    geometry
+      .update(setting, leds)
+      .map_err(|e| format!("invalid geometry: {}", e))?;
+  }
+
+  let frames = capture(show.as_mut(), &geometry, &opts.capture);
+
+  if opts.check {
+    return check_png(&frames, &opts.output);
+  }
+  match opts.output.extension().and_then(|e| e.to_str()) {
+    Some("png") => write_png(&frames, &opts),
+    Some("gif") => write_gif(&frames, &opts),
+    _ => Err("output must be a `.png` or `.gif` file".into()),
+  }
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("error: {}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,113 @@
+//! Shared code of the host tools.
+
+use std::{fs::File, path::Path};
+
+use arclib::{
+  color::{NormRgbw, RawChannel},
+  geometry::Geometry,
+  output::{Output, OutputSettings},
+  show::{FrameContext, Memory, Micros, Show},
+  strip::StripConfig,
+};
+
+/// Channels of a golden image may differ this much from the rendered ones,
+/// as the trigonometric functions round differently depending on the platform.
+pub const TOLERANCE: u8 = 2;
+
+/// Color as it would be seen on a screen, the white channel is approximated by the rgb channels.
+pub fn preview_rgb([r, g, b, w]: [RawChannel; 4]) -> [u8; 3] {
+  [
+    r.saturating_add(w),
+    g.saturating_add(w),
+    b.saturating_add(w),
+  ]
+}
+
+/// The output stage of the default strip, shortened or extended to `nlights`.
+pub fn strip_output(nlights: usize) -> Output {
+  Output::new(vec![StripConfig::default().order.has_white(); nlights])
+}
+
+/// How a show is captured by `capture`.
+pub struct Capture {
+  pub seconds: u64,
+  /// Captured frames per second.
+  pub fps: u64,
+  pub leds: usize,
+  pub seed: u64,
+  pub output: OutputSettings,
+}
+
+/// Runs `show` in simulated time and captures `fps` frames per second, as they are sent to the strip.
+/// Frames are rendered whenever the show asks for it, like the pico would.
+pub fn capture(show: &mut dyn Show, geometry: &Geometry, capture: &Capture) -> Vec<Vec<[u8; 3]>> {
+  let mut frame = vec![NormRgbw::NONE; capture.leds];
+  let mut output = strip_output(capture.leds);
+  let mut last_frame: Micros = 0;
+  let mut next_frame: Micros = 0;
+  let memory = Memory::default();
+
+  let ncaptures = capture.seconds * capture.fps;
+  (0..ncaptures)
+    .map(|i| {
+      let capture_time = i * 1_000_000 / capture.fps;
+      while next_frame <= capture_time {
+        let ctx = FrameContext {
+          time: next_frame,
+          delta: next_frame - last_frame,
+          action: None,
+          seed: capture.seed,
+          geometry,
+          memory: &memory,
+        };
+        last_frame = next_frame;
+        next_frame += show.render(&ctx, &mut frame).max(1);
+      }
+      output.convert(&frame, &capture.output);
+      output.raw().iter().map(|&raw| preview_rgb(raw)).collect()
+    })
+    .collect()
+}
+
+/// Compares the frames against a PNG strip written earlier, with `TOLERANCE`.
+pub fn check_png(frames: &[Vec<[u8; 3]>], path: &Path) -> Result<(), String> {
+  let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+  let mut reader = png::Decoder::new(file)
+    .read_info()
+    .map_err(|e| e.to_string())?;
+  let mut golden = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut golden).map_err(|e| e.to_string())?;
+
+  let leds = frames.first().map_or(0, Vec::len);
+  if info.color_type != png::ColorType::Rgb || info.bit_depth != png::BitDepth::Eight {
+    return Err("golden image must be 8 bit RGB".into());
+  }
+  if info.width as usize != leds || info.height as usize != frames.len() {
+    return Err(format!(
+      "size mismatch: golden image is {}x{}, rendered {}x{}",
+      info.width,
+      info.height,
+      leds,
+      frames.len()
+    ));
+  }
+
+  let golden = golden[..info.buffer_size()].chunks(3);
+  let rendered = frames.iter().flatten();
+  for (i, (golden, rendered)) in golden.zip(rendered).enumerate() {
+    let close = golden
+      .iter()
+      .zip(rendered)
+      .all(|(g, r)| g.abs_diff(*r) <= TOLERANCE);
+    if !close {
+      return Err(format!(
+        "mismatch in frame {} at light {}: expected {:?}, rendered {:?}",
+        i / leds,
+        i % leds,
+        golden,
+        rendered
+      ));
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,208 @@
+//! Terminal simulator for the LED strip.
+//!
+//! Runs the arcus shows on the host and draws the strip using ANSI true colors,
+//! after the same calibration, dithering and power limit as on the pico.
+//! The keys are the same as the ones accepted over UART.
+//! Shows are blended into each other like on the pico, `--transition <style> [ms]` changes how.
+
+use std::{
+  io::{self, Write},
+  time::{Instant, SystemTime},
+};
+
+use arclib::{
+  color::{NormRgbw, RawChannel},
+  control::{self, Command, InputState},
+  geometry::Geometry,
+  output::{Output, OutputSettings},
+  show::{Action, FrameContext, Memory, Show, Transition, FRAME_TIME},
+  strip::StripConfig,
+  ONE, ZERO,
+};
+use arcus_sim::{preview_rgb, strip_output};
+use crossterm::{
+  cursor,
+  event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+  execute, queue, style, terminal,
+};
+
+/// Number of lights drawn per terminal row.
+const ROW_LEN: usize = 60;
+
+struct RunningShow {
+  show: Box<dyn Show + Send>,
+  start: Instant,
+  last_frame: Instant,
+  seed: u64,
+}
+
+struct Simulator {
+  input_state: InputState,
+  /// The brightness, the rest stays at the defaults of the pico.
+  settings: OutputSettings,
+  output: Output,
+  running: Option<RunningShow>,
+  /// Action which will be passed to the show with the next frame.
+  action: Option<Action>,
+  frame: Vec<NormRgbw>,
+  geometry: Geometry,
+  transition: Transition,
+  /// Kept while the simulator runs, it isn't saved.
+  memory: Memory,
+  next_frame: Instant,
+}
+
+impl Simulator {
+  fn new(transition: Transition) -> Self {
+    Self {
+      input_state: InputState::default(),
+      settings: OutputSettings::default(),
+      output: strip_output(StripConfig::default().nlights),
+      running: None,
+      action: None,
+      frame: vec![NormRgbw::NONE; StripConfig::default().nlights],
+      geometry: Geometry::linear(StripConfig::default().nlights),
+      transition,
+      memory: Memory::default(),
+      next_frame: Instant::now(),
+    }
+  }
+
+  /// Does the same as `input_task` does on the pico.
+  fn handle(&mut self, action: Action) {
+    match self.input_state.handle(action) {
+      Some(Command::Stop) => self.running = None,
+      Some(Command::SetShow(spec)) => {
+        let Ok(show) = spec.create() else { return };
+        let now = Instant::now();
+        let seed = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_micros() as u64;
+        self.running = Some(RunningShow {
+          show: self.transition.apply(show),
+          start: now,
+          last_frame: now,
+          seed,
+        });
+        self.next_frame = now;
+      }
+      Some(Command::SetBrightness(brightness)) => self.settings.brightness = brightness,
+      Some(Command::ChangeBrightness(delta)) => {
+        self.settings.brightness = (self.settings.brightness + delta).clamp(ZERO, ONE)
+      }
+      Some(Command::Forward(action)) => self.action = Some(action),
+      None => {}
+    }
+  }
+
+  /// Does the same as `show_task` does on the pico.
+  fn render(&mut self) {
+    let now = Instant::now();
+    let next_frame = match &mut self.running {
+      Some(running) => {
+        let ctx = FrameContext {
+          time: (now - running.start).as_micros() as u64,
+          delta: (now - running.last_frame).as_micros() as u64,
+          action: self.action.take(),
+          seed: running.seed,
+          geometry: &self.geometry,
+          memory: &self.memory,
+        };
+        running.last_frame = now;
+        running.show.render(&ctx, &mut self.frame)
+      }
+      None => FRAME_TIME,
+    };
+    self.output.convert(&self.frame, &self.settings);
+    self.next_frame = now + std::time::Duration::from_micros(next_frame);
+  }
+
+  fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+    queue!(out, cursor::MoveTo(0, 0))?;
+    for row in self.output.raw().chunks(ROW_LEN) {
+      for &raw in row {
+        queue!(
+          out,
+          style::SetForegroundColor(terminal_color(raw)),
+          style::Print('█'),
+        )?;
+      }
+      queue!(out, style::ResetColor, cursor::MoveToNextLine(1))?;
+    }
+    queue!(
+      out,
+      cursor::MoveToNextLine(1),
+      style::Print(format!("brightness: {:.2}", self.settings.brightness)),
+      terminal::Clear(terminal::ClearType::UntilNewLine),
+      cursor::MoveToNextLine(1),
+      style::Print("p: select show, t: select brightness, [/]: dim, s: stop, q: quit"),
+    )?;
+    out.flush()
+  }
+}
+
+fn terminal_color(raw: [RawChannel; 4]) -> style::Color {
+  let [r, g, b] = preview_rgb(raw);
+  style::Color::Rgb { r, g, b }
+}
+
+fn run(out: &mut impl Write, transition: Transition) -> io::Result<()> {
+  let mut sim = Simulator::new(transition);
+  loop {
+    let timeout = sim.next_frame.saturating_duration_since(Instant::now());
+    if event::poll(timeout)? {
+      if let Event::Key(KeyEvent {
+        code, modifiers, ..
+      }) = event::read()?
+      {
+        match code {
+          KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+          KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+          KeyCode::Char(c) => {
+            if let Some(action) = control::action_from_key(c.encode_utf8(&mut [0; 4])) {
+              sim.handle(action);
+            }
+          }
+          _ => {}
+        }
+      }
+      continue;
+    }
+
+    sim.render();
+    sim.draw(out)?;
+  }
+}
+
+/// The transition given as `--transition <style> [ms]`, the one of the pico otherwise.
+fn parse_transition(mut args: impl Iterator<Item = String>) -> Result<Transition, String> {
+  let mut transition = Transition::default();
+  match args.next().as_deref() {
+    None => {}
+    Some("--transition") => {
+      let setting: Vec<String> = args.collect();
+      transition.update(&setting.join(" "))?;
+    }
+    Some(_) => {
+      return Err("usage: arcus-sim [--transition <off|crossfade|wipe|dissolve> [ms]]".into())
+    }
+  }
+  Ok(transition)
+}
+
+fn main() -> io::Result<()> {
+  let transition = match parse_transition(std::env::args().skip(1)) {
+    Ok(transition) => transition,
+    Err(e) => {
+      eprintln!("{}", e);
+      std::process::exit(2);
+    }
+  };
+  let mut stdout = io::stdout();
+  terminal::enable_raw_mode()?;
+  execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+  let result = run(&mut stdout, transition);
+
+  execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+  terminal::disable_raw_mode()?;
+  result
+}
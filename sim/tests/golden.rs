@@ -0,0 +1,46 @@
+//! Compares the shows against the golden images in `golden/`.
+//!
+//! After an intended change of a show, its image is rendered again with
+//! `render <show> golden/<show>.png --seconds <seconds> --fps <fps>`.
+
+use std::path::Path;
+
+use arclib::{
+  control::show_from_name, geometry::Geometry, output::OutputSettings, strip::StripConfig,
+};
+use arcus_sim::{capture, check_png, Capture};
+
+/// The shows with their duration and captured frames per second.
+const GOLDEN: &[(&str, u64, u64)] = &[
+  ("demo", 10, 10),
+  ("gradient", 1, 1),
+  ("clock", 10, 10),
+  ("rgb-clock", 10, 4),
+];
+
+#[test]
+fn shows_match_their_golden_images() {
+  let leds = StripConfig::default().nlights;
+  let geometry = Geometry::linear(leds);
+  for &(name, seconds, fps) in GOLDEN {
+    let mut show = show_from_name(name).unwrap();
+    // the defaults of `render`
+    let settings = Capture {
+      seconds,
+      fps,
+      leds,
+      seed: 0,
+      output: OutputSettings {
+        dithering: false,
+        ..OutputSettings::default()
+      },
+    };
+    let frames = capture(show.as_mut(), &geometry, &settings);
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("golden")
+      .join(format!("{}.png", name));
+    if let Err(e) = check_png(&frames, &golden) {
+      panic!("{}: {}", name, e);
+    }
+  }
+}
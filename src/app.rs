@@ -9,19 +9,24 @@ pub use inner_app::*;
 mod inner_app {
   use embedded_hal::digital::v2::OutputPin;
 
+  use arclib::{geometry::Geometry, strip::StripConfig};
   use rp2040_monotonic::Rp2040Monotonic;
   use rp_pico::hal::{self, clocks, gpio, Sio};
+  use usb_device::class_prelude::UsbBusAllocator;
 
   use crate::{
     config::Config,
+    driver::{self, ShowCancellationToken, StreamedFrame},
     input::{
-      remote::{RemoteInput, RemoteTask},
+      remote::{Action, RemoteInput, RemoteTask},
       uart::UartTask,
+      usb::UsbTask,
       InputTask,
     },
-    show::{self, ShowCancellationToken},
+    light::OutputConfig,
+    storage::SettingsTask,
     uprintln,
-    util::uart::init_uart,
+    util::{uart::init_uart, usb::init_usb},
     ALLOCATOR,
   };
 
@@ -39,17 +44,20 @@ mod inner_app {
     config: Config,
     remote_input: RemoteInput,
     show_cancellation_token: ShowCancellationToken,
+    streamed_frame: StreamedFrame,
   }
 
   #[local]
   struct Local {
-    show_task: show::ShowTask,
+    show_task: driver::ShowTask,
     input_task: InputTask,
     remote_task: RemoteTask,
     uart_task: UartTask,
+    usb_task: UsbTask,
+    settings_task: SettingsTask,
   }
 
-  #[init]
+  #[init(local = [usb_bus: Option<UsbBusAllocator<hal::usb::UsbBus>> = None])]
   fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
     let heap_start = cortex_m_rt::heap_start() as usize;
     let heap_size = 200 * 1024;
@@ -85,14 +93,37 @@ mod inner_app {
     );
     uprintln!("uart initialized.");
 
+    *ctx.local.usb_bus = Some(UsbBusAllocator::new(hal::usb::UsbBus::new(
+      ctx.device.USBCTRL_REGS,
+      ctx.device.USBCTRL_DPRAM,
+      clocks.usb_clock,
+      true,
+      &mut ctx.device.RESETS,
+    )));
+    init_usb(ctx.local.usb_bus.as_ref().unwrap());
+    uprintln!("usb initialized.");
+
     let mut led: LedPin = pins.led.into_push_pull_output();
     led.set_high().unwrap();
 
-    let config = Config::default();
-
-    let show_task = show::ShowTask::init(
-      pins.gpio2.into_mode(),
+    // more strips can be connected to other pins, they are concatenated in order
+    let outputs = [OutputConfig {
+      pin: 2,
+      strip: StripConfig::default(),
+    }];
+
+    let nlights = outputs.iter().map(|o| o.strip.nlights).sum();
+    let mut config = Config {
+      nlights,
+      geometry: Geometry::linear(nlights),
+      ..Config::default()
+    };
+    let settings_task = SettingsTask::init(&mut config);
+    let show_task = driver::ShowTask::init(
+      &outputs,
       ctx.device.PIO0,
+      ctx.device.PIO1,
+      ctx.device.DMA,
       &clocks.system_clock,
       &mut ctx.device.RESETS,
     );
@@ -103,7 +134,8 @@ mod inner_app {
     let remote_input = RemoteInput::default();
     let remote_task = RemoteTask::init(pins.gpio3.into_floating_input());
 
-    let uart_task = UartTask::init();
+    let uart_task = UartTask::init(nlights);
+    let usb_task = UsbTask::init(nlights);
 
     let mono = Monotonic::new(ctx.device.TIMER);
 
@@ -112,40 +144,51 @@ mod inner_app {
         config,
         remote_input,
         show_cancellation_token,
+        streamed_frame: StreamedFrame::default(),
       },
       Local {
         show_task,
         input_task,
         remote_task,
         uart_task,
+        usb_task,
+        settings_task,
       },
       init::Monotonics(mono),
     )
   }
 
   use crate::{
-    input::{input_task, remote::remote_task, uart::uart_task},
-    show::show_task,
+    driver::show_task,
+    input::{input_task, remote::remote_task, uart::uart_task, usb::usb_task},
+    light::{lights_dma_task, lights_latch_task},
+    storage::settings_task,
   };
   extern "Rust" {
     #[task(
         priority = 1,
-        shared = [show_cancellation_token, config, remote_input],
+        shared = [show_cancellation_token, config, remote_input, streamed_frame],
         local = [show_task],
     )]
     fn show_task(ctx: show_task::Context);
 
     #[task(
         priority = 2,
+        capacity = 8,
         shared = [remote_input, config, show_cancellation_token],
         local = [input_task],
     )]
-    fn input_task(ctx: input_task::Context);
+    fn input_task(ctx: input_task::Context, action: Action);
+
+    #[task(binds = DMA_IRQ_0, priority = 3)]
+    fn lights_dma_task(ctx: lights_dma_task::Context);
+
+    #[task(priority = 3, capacity = 8)]
+    fn lights_latch_task(ctx: lights_latch_task::Context, output: usize);
 
     #[task(
         binds = IO_IRQ_BANK0,
         priority = 3,
-        shared = [remote_input],
         local = [remote_task],
     )]
     fn remote_task(ctx: remote_task::Context);
@@ -153,9 +196,20 @@ mod inner_app {
     #[task(
         binds = UART0_IRQ,
         priority = 4,
-        shared = [remote_input],
+        shared = [remote_input, config, show_cancellation_token, streamed_frame],
         local = [uart_task],
     )]
     fn uart_task(ctx: uart_task::Context);
+
+    #[task(
+        binds = USBCTRL_IRQ,
+        priority = 4,
+        shared = [remote_input, config, show_cancellation_token, streamed_frame],
+        local = [usb_task],
+    )]
+    fn usb_task(ctx: usb_task::Context);
+
+    #[task(priority = 1, shared = [config], local = [settings_task])]
+    fn settings_task(ctx: settings_task::Context);
   }
 }
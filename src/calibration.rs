@@ -0,0 +1,233 @@
+//! Correction of the colors for the actual LEDs.
+
+use core::fmt;
+
+use crate::{
+  color::{NormRgbw, WhiteExtraction},
+  nl, Fix32, ONE, ZERO,
+};
+
+/// Number of segments of the gamma table.
+const SEGMENTS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+  /// Exponent of the gamma curve, `1` is linear.
+  pub gamma: Fix32,
+  /// Factors between 0 and 1 for the channels `[r, g, b, w]`, applied after the gamma curve.
+  /// Used to match the white point of the channels, e.g. the w channel with RGB white.
+  pub scale: [Fix32; 4],
+  /// Only used for strips with a white LED, before the gamma curve.
+  pub white: WhiteExtraction,
+}
+
+impl Default for Calibration {
+  fn default() -> Self {
+    Self {
+      gamma: ONE,
+      scale: [ONE; 4],
+      white: WhiteExtraction::Off,
+    }
+  }
+}
+
+impl Calibration {
+  pub fn table(self) -> CalibrationTable {
+    CalibrationTable::new(self)
+  }
+
+  /// Changes a single setting, given as `gamma <exponent>`, `<r|g|b|w> <factor>`
+  /// or `white <off|on|<r> <g> <b>>` with the color of the white LED.
+  pub fn update(&mut self, setting: &str) -> Result<(), &'static str> {
+    let mut words = setting.split_whitespace();
+    let name = words.next().ok_or("expected `<setting> <value>`")?;
+    let mut values = [ZERO; 3];
+    let mut nvalues = 0;
+    let mut white = None;
+    for word in words {
+      match word {
+        "off" if name == "white" => white = Some(WhiteExtraction::Off),
+        "on" if name == "white" => white = Some(WhiteExtraction::NEUTRAL),
+        _ => {
+          let value = values.get_mut(nvalues).ok_or("too many values")?;
+          *value = word.parse().map_err(|_| "invalid number")?;
+          nvalues += 1;
+        }
+      }
+    }
+    let value = values[0];
+
+    let channel = match (name, nvalues, white) {
+      ("white", 0, Some(white)) => {
+        self.white = white;
+        return Ok(());
+      }
+      ("white", 3, None) => {
+        if !values.iter().all(|v| (ZERO..=ONE).contains(v)) {
+          return Err("white LED channels must be between 0 and 1");
+        }
+        if values.iter().all(|&v| v == ZERO) {
+          return Err("white LED color must not be black");
+        }
+        let [r, g, b] = values;
+        self.white = WhiteExtraction::On(NormRgbw::new(r, g, b, ZERO));
+        return Ok(());
+      }
+      ("white", _, _) => return Err("expected `white <off|on|<r> <g> <b>>`"),
+      (_, 1, None) => name,
+      _ => return Err("expected `<setting> <value>`"),
+    };
+    let channel = match channel {
+      "gamma" => {
+        if !(nl!(0.1)..=nl!(5)).contains(&value) {
+          return Err("gamma must be between 0.1 and 5");
+        }
+        self.gamma = value;
+        return Ok(());
+      }
+      "r" => 0,
+      "g" => 1,
+      "b" => 2,
+      "w" => 3,
+      _ => return Err("unknown setting, expected gamma, r, g, b, w or white"),
+    };
+    if !(ZERO..=ONE).contains(&value) {
+      return Err("factor must be between 0 and 1");
+    }
+    self.scale[channel] = value;
+    Ok(())
+  }
+}
+
+impl fmt::Display for Calibration {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let [r, g, b, w] = self.scale;
+    write!(f, "gamma {} r {} g {} b {} w {}", self.gamma, r, g, b, w)?;
+    match self.white {
+      WhiteExtraction::Off => write!(f, " white off"),
+      WhiteExtraction::On(led) => write!(f, " white {} {} {}", led.r, led.g, led.b),
+    }
+  }
+}
+
+/// Precomputed gamma curve of a calibration.
+pub struct CalibrationTable {
+  calibration: Calibration,
+  /// `x^gamma` at `SEGMENTS + 1` equidistant points.
+  lut: [Fix32; SEGMENTS + 1],
+}
+
+impl CalibrationTable {
+  pub fn new(calibration: Calibration) -> Self {
+    let gamma = calibration.gamma.to_num::<f32>();
+    let mut lut = [ZERO; SEGMENTS + 1];
+    for (i, entry) in lut.iter_mut().enumerate() {
+      let x = i as f32 / SEGMENTS as f32;
+      *entry = nl!(libm::powf(x, gamma));
+    }
+    Self { calibration, lut }
+  }
+
+  pub fn calibration(&self) -> Calibration {
+    self.calibration
+  }
+
+  /// Applies the white extraction if the strip has a white LED,
+  /// then the gamma curve and then the channel factors.
+  /// Channels get clamped to `[0, 1]`.
+  #[must_use]
+  pub fn apply(&self, mut color: NormRgbw, has_white: bool) -> NormRgbw {
+    if has_white {
+      color = self.calibration.white.apply(color);
+    }
+    let mut channels = color.into_channel_array();
+    for (c, scale) in channels.iter_mut().zip(self.calibration.scale) {
+      *c = self.gamma(*c) * scale;
+    }
+    NormRgbw::from_channel_array(channels)
+  }
+
+  /// Linear interpolation of the table.
+  fn gamma(&self, x: Fix32) -> Fix32 {
+    let x = x.clamp(ZERO, ONE) * nl!(SEGMENTS);
+    let i = x.int().to_num::<usize>();
+    if i == SEGMENTS {
+      return self.lut[SEGMENTS];
+    }
+    let t = x.frac();
+    self.lut[i] + t * (self.lut[i + 1] - self.lut[i])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: NormRgbw, b: NormRgbw) {
+    for (a, b) in a
+      .into_channel_array()
+      .into_iter()
+      .zip(b.into_channel_array())
+    {
+      assert!((a - b).abs() < nl!(0.001), "{:?} != {:?}", a, b);
+    }
+  }
+
+  #[test]
+  fn linear_table_keeps_the_colors() {
+    let table = Calibration::default().table();
+    let color = NormRgbw::new(nl!(0.1), nl!(0.5), nl!(0.77), ONE);
+    assert_close(table.apply(color, true), color);
+  }
+
+  #[test]
+  fn applies_the_gamma_curve() {
+    let mut calibration = Calibration::default();
+    calibration.update("gamma 2").unwrap();
+    let table = calibration.table();
+    assert_eq!(table.gamma(ZERO), ZERO);
+    assert_eq!(table.gamma(ONE), ONE);
+    assert_eq!(table.gamma(nl!(2)), ONE);
+    assert!((table.gamma(nl!(0.5)) - nl!(0.25)).abs() < nl!(0.001));
+    // in between the points of the table
+    assert!((table.gamma(nl!(0.3001)) - nl!(0.09006)).abs() < nl!(0.001));
+  }
+
+  #[test]
+  fn scales_the_channels() {
+    let mut calibration = Calibration::default();
+    calibration.update("g 0.5").unwrap();
+    let table = calibration.table();
+    assert_close(
+      table.apply(NormRgbw::RGB, false),
+      NormRgbw::new(ONE, nl!(0.5), ONE, ZERO),
+    );
+  }
+
+  #[test]
+  fn extracts_white_only_with_a_white_led() {
+    let calibration = Calibration {
+      white: WhiteExtraction::NEUTRAL,
+      ..Calibration::default()
+    };
+    let table = calibration.table();
+    let color = NormRgbw::new(ONE, nl!(0.5), nl!(0.5), ZERO);
+    assert_close(
+      table.apply(color, true),
+      NormRgbw::new(nl!(0.5), ZERO, ZERO, nl!(0.5)),
+    );
+    assert_close(table.apply(color, false), color);
+  }
+
+  #[test]
+  fn rejects_a_black_white_led() {
+    let mut calibration = Calibration::default();
+    assert!(calibration.update("white 0 0 0").is_err());
+    assert_eq!(calibration.white, WhiteExtraction::Off);
+    calibration.update("white 1 0.8 0").unwrap();
+    assert_eq!(
+      calibration.white,
+      WhiteExtraction::On(NormRgbw::new(ONE, nl!(0.8), ZERO, ZERO))
+    );
+  }
+}
@@ -0,0 +1,655 @@
+use core::fmt;
+
+use crate::{nl, strip::ChannelOrder, Fix32, ONE, ZERO};
+
+pub type RawColor = u32;
+pub type RawChannel = u8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NormRgbw {
+  pub r: Fix32,
+  pub g: Fix32,
+  pub b: Fix32,
+  pub w: Fix32,
+}
+
+impl NormRgbw {
+  pub const fn new(r: Fix32, g: Fix32, b: Fix32, w: Fix32) -> Self {
+    Self { r, g, b, w }
+  }
+
+  pub const fn into_channel_array(self) -> [Fix32; 4] {
+    [self.r, self.g, self.b, self.w]
+  }
+  pub const fn from_channel_array(channels: [Fix32; 4]) -> Self {
+    Self::new(channels[0], channels[1], channels[2], channels[3])
+  }
+
+  pub fn into_u8_channel_array(self) -> [u8; 4] {
+    denormalize(self.into_channel_array())
+  }
+  pub fn from_u8_channel_array(channels: [u8; 4]) -> Self {
+    Self::from_channel_array(normalize(channels))
+  }
+
+  pub fn into_u32(self, order: ChannelOrder) -> u32 {
+    pack(self.into_u8_channel_array(), order)
+  }
+  pub fn from_u32(value: u32, order: ChannelOrder) -> Self {
+    Self::from_u8_channel_array(unpack(value, order))
+  }
+
+  /// Parses `#rrggbb` or `#rrggbbww`, the `#` is optional.
+  pub fn from_hex(hex: &str) -> Option<Self> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+      return None;
+    }
+    let mut channels = [0; 4];
+    for (channel, i) in channels.iter_mut().zip((0..hex.len()).step_by(2)) {
+      *channel = u8::from_str_radix(&hex[i..i + 2], 16).ok()?;
+    }
+    Some(Self::from_u8_channel_array(channels))
+  }
+}
+
+/// Formats as `rrggbb`, followed by `ww` if the white channel is on.
+impl fmt::LowerHex for NormRgbw {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // rounded, so parsed colors are formatted the same way again
+    let [r, g, b, w] = self
+      .into_channel_array()
+      .map(|c| (c.clamp(ZERO, ONE) * nl!(255u8)).round().to_num::<u8>());
+    write!(f, "{:02x}{:02x}{:02x}", r, g, b)?;
+    if w != 0 {
+      write!(f, "{:02x}", w)?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NormHsv {
+  pub hue: Fix32,
+  pub sat: Fix32,
+  pub val: Fix32,
+}
+
+impl NormHsv {
+  pub fn new(hue: Fix32, sat: Fix32, val: Fix32) -> Self {
+    Self { hue, sat, val }
+  }
+
+  pub fn mix(self, other: Self) -> Self {
+    self.gradient(other, ONE / nl!(2))
+  }
+
+  /// Takes the shorter way around the hue circle.
+  pub fn gradient(self, other: Self, t: Fix32) -> Self {
+    Self::new(
+      hue_gradient(self.hue, self.sat, other.hue, other.sat, t),
+      (ONE - t) * self.sat + t * other.sat,
+      (ONE - t) * self.val + t * other.val,
+    )
+  }
+}
+
+/// Interpolates between hues in `[0, 1)` along the shorter way around the circle.
+/// The hue of a color without saturation/chroma is meaningless, so the other one is used.
+fn hue_gradient(from: Fix32, from_sat: Fix32, to: Fix32, to_sat: Fix32, t: Fix32) -> Fix32 {
+  let (from, to) = match (from_sat == ZERO, to_sat == ZERO) {
+    (true, false) => (to, to),
+    (false, true) => (from, from),
+    _ => (from, to),
+  };
+  let mut delta = (to - from).rem_euclid(ONE);
+  if delta > ONE / 2 {
+    delta -= ONE;
+  }
+  (from + t * delta).rem_euclid(ONE)
+}
+
+impl From<NormHsv> for NormRgbw {
+  #[allow(clippy::zero_prefixed_literal)]
+  fn from(hsv: NormHsv) -> Self {
+    let NormHsv { mut hue, sat, val } = hsv;
+    hue *= nl!(360u16);
+    let c = val * sat;
+    let v = (hue / nl!(60u16)) % nl!(2u16) - ONE;
+    let v = if v < ZERO { -v } else { v };
+    let x = c * (ONE - v);
+    let m = val - c;
+    let (r, g, b) = if hue < nl!(60u16) {
+      (c, x, ZERO)
+    } else if hue < nl!(120u16) {
+      (x, c, ZERO)
+    } else if hue < nl!(180u16) {
+      (ZERO, c, x)
+    } else if hue < nl!(240u16) {
+      (ZERO, x, c)
+    } else if hue < nl!(300u16) {
+      (x, ZERO, c)
+    } else {
+      (c, ZERO, x)
+    };
+
+    Self::new(r + m, g + m, b + m, ZERO)
+  }
+}
+
+impl From<NormRgbw> for NormHsv {
+  fn from(norm_color: NormRgbw) -> Self {
+    let [r, g, b] = [norm_color.r, norm_color.g, norm_color.b];
+    let cmax = r.max(g).max(b);
+    let cmin = r.min(g).min(b);
+    let delta = cmax - cmin;
+
+    let hue = if delta == ZERO {
+      ZERO
+    } else {
+      nl!(60) / nl!(360)
+        * match cmax {
+          c if c == r => ((g - b) / delta) % nl!(6),
+          c if c == g => (b - r) / delta + nl!(2),
+          c if c == b => (r - g) / delta + nl!(4),
+          _ => unreachable!(),
+        }
+    };
+    let sat = if cmax == ZERO { ZERO } else { delta / cmax };
+    let val = cmax;
+
+    Self::new(hue, sat, val)
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NormHsl {
+  pub hue: Fix32,
+  pub sat: Fix32,
+  pub light: Fix32,
+}
+
+impl NormHsl {
+  pub fn new(hue: Fix32, sat: Fix32, light: Fix32) -> Self {
+    Self { hue, sat, light }
+  }
+
+  pub fn mix(self, other: Self) -> Self {
+    self.gradient(other, ONE / nl!(2))
+  }
+
+  /// Takes the shorter way around the hue circle.
+  pub fn gradient(self, other: Self, t: Fix32) -> Self {
+    Self::new(
+      hue_gradient(self.hue, self.sat, other.hue, other.sat, t),
+      (ONE - t) * self.sat + t * other.sat,
+      (ONE - t) * self.light + t * other.light,
+    )
+  }
+}
+
+impl From<NormHsl> for NormHsv {
+  fn from(hsl: NormHsl) -> Self {
+    let NormHsl { hue, sat, light } = hsl;
+    let val = light + sat * light.min(ONE - light);
+    let sat = if val == ZERO {
+      ZERO
+    } else {
+      nl!(2) * (ONE - light / val)
+    };
+    Self::new(hue, sat, val)
+  }
+}
+
+impl From<NormHsv> for NormHsl {
+  fn from(hsv: NormHsv) -> Self {
+    let NormHsv { hue, sat, val } = hsv;
+    let light = val * (ONE - sat / nl!(2));
+    let sat = if light == ZERO || light == ONE {
+      ZERO
+    } else {
+      (val - light) / light.min(ONE - light)
+    };
+    Self::new(hue, sat, light)
+  }
+}
+
+impl From<NormHsl> for NormRgbw {
+  fn from(hsl: NormHsl) -> Self {
+    NormHsv::from(hsl).into()
+  }
+}
+
+impl From<NormRgbw> for NormHsl {
+  fn from(color: NormRgbw) -> Self {
+    NormHsv::from(color).into()
+  }
+}
+
+/// The perceptual OKLab color space, see <https://bottosson.github.io/posts/oklab/>.
+/// The RGB channels are taken as linear light, like the LEDs emit it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OkLab {
+  pub l: Fix32,
+  pub a: Fix32,
+  pub b: Fix32,
+}
+
+impl OkLab {
+  pub fn new(l: Fix32, a: Fix32, b: Fix32) -> Self {
+    Self { l, a, b }
+  }
+
+  pub fn mix(self, other: Self) -> Self {
+    self.gradient(other, ONE / nl!(2))
+  }
+
+  pub fn gradient(self, other: Self, t: Fix32) -> Self {
+    Self::new(
+      (ONE - t) * self.l + t * other.l,
+      (ONE - t) * self.a + t * other.a,
+      (ONE - t) * self.b + t * other.b,
+    )
+  }
+}
+
+/// OKLab in polar coordinates, with the hue between 0 and 1 like `NormHsv`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OkLch {
+  pub l: Fix32,
+  pub chroma: Fix32,
+  pub hue: Fix32,
+}
+
+impl OkLch {
+  pub fn new(l: Fix32, chroma: Fix32, hue: Fix32) -> Self {
+    Self { l, chroma, hue }
+  }
+
+  pub fn mix(self, other: Self) -> Self {
+    self.gradient(other, ONE / nl!(2))
+  }
+
+  /// Takes the shorter way around the hue circle.
+  pub fn gradient(self, other: Self, t: Fix32) -> Self {
+    Self::new(
+      (ONE - t) * self.l + t * other.l,
+      (ONE - t) * self.chroma + t * other.chroma,
+      hue_gradient(self.hue, self.chroma, other.hue, other.chroma, t),
+    )
+  }
+}
+
+/// Ignores the w channel.
+impl From<NormRgbw> for OkLab {
+  fn from(color: NormRgbw) -> Self {
+    let NormRgbw { r, g, b, .. } = color;
+    let l = nl!(0.4122214708) * r + nl!(0.5363325363) * g + nl!(0.0514459929) * b;
+    let m = nl!(0.2119034982) * r + nl!(0.6806995451) * g + nl!(0.1073969566) * b;
+    let s = nl!(0.0883024619) * r + nl!(0.2817188376) * g + nl!(0.6299787005) * b;
+    let (l, m, s) = (cbrt(l), cbrt(m), cbrt(s));
+    Self::new(
+      nl!(0.2104542553) * l + nl!(0.7936177850) * m - nl!(0.0040720468) * s,
+      nl!(1.9779984951) * l - nl!(2.4285922050) * m + nl!(0.4505937099) * s,
+      nl!(0.0259040371) * l + nl!(0.7827717662) * m - nl!(0.8086757660) * s,
+    )
+  }
+}
+
+/// Colors outside of the RGB gamut get clamped.
+impl From<OkLab> for NormRgbw {
+  fn from(lab: OkLab) -> Self {
+    let OkLab { l, a, b } = lab;
+    let l_ = l + nl!(0.3963377774) * a + nl!(0.2158037573) * b;
+    let m_ = l - nl!(0.1055613458) * a - nl!(0.0638541728) * b;
+    let s_ = l - nl!(0.0894841775) * a - nl!(1.2914855480) * b;
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+    let r = nl!(4.0767416621) * l - nl!(3.3077115913) * m + nl!(0.2309699292) * s;
+    let g = nl!(-1.2684380046) * l + nl!(2.6097574011) * m - nl!(0.3413193965) * s;
+    let b = nl!(-0.0041960863) * l - nl!(0.7034186147) * m + nl!(1.7076147010) * s;
+    Self::new(
+      r.clamp(ZERO, ONE),
+      g.clamp(ZERO, ONE),
+      b.clamp(ZERO, ONE),
+      ZERO,
+    )
+  }
+}
+
+impl From<OkLab> for OkLch {
+  fn from(lab: OkLab) -> Self {
+    let OkLab { l, a, b } = lab;
+    let chroma = cordic::sqrt(a * a + b * b);
+    let hue = if chroma == ZERO {
+      ZERO
+    } else {
+      (cordic::atan2(b, a) / nl!(fixed::consts::TAU)).rem_euclid(ONE)
+    };
+    Self::new(l, chroma, hue)
+  }
+}
+
+impl From<OkLch> for OkLab {
+  fn from(lch: OkLch) -> Self {
+    let OkLch { l, chroma, hue } = lch;
+    // angle in `[-pi, pi)`
+    let mut hue = hue.rem_euclid(ONE);
+    if hue >= ONE / 2 {
+      hue -= ONE;
+    }
+    let (sin, cos) = cordic::sin_cos(hue * nl!(fixed::consts::TAU));
+    Self::new(l, chroma * cos, chroma * sin)
+  }
+}
+
+impl From<NormRgbw> for OkLch {
+  fn from(color: NormRgbw) -> Self {
+    OkLab::from(color).into()
+  }
+}
+
+impl From<OkLch> for NormRgbw {
+  fn from(lch: OkLch) -> Self {
+    OkLab::from(lch).into()
+  }
+}
+
+/// Cube root by Newton's method, starting at a power of two close to the root.
+fn cbrt(x: Fix32) -> Fix32 {
+  if x <= ZERO {
+    return ZERO;
+  }
+  const FRAC_BITS: i32 = 16;
+  // `x` is in `[2^e, 2^(e + 1))`
+  let e = 31 - FRAC_BITS - x.leading_zeros() as i32;
+  let mut y = Fix32::from_bits(1 << (FRAC_BITS + e.div_euclid(3)));
+  for _ in 0..6 {
+    let y2 = y * y;
+    if y2 == ZERO {
+      break;
+    }
+    y = (nl!(2) * y + x / y2) / nl!(3);
+  }
+  y
+}
+
+/// Color definitions
+impl NormRgbw {
+  pub const RED: Self = Self::new(ONE, ZERO, ZERO, ZERO);
+  pub const GREEN: Self = Self::new(ZERO, ONE, ZERO, ZERO);
+  pub const BLUE: Self = Self::new(ZERO, ZERO, ONE, ZERO);
+  pub const WHITE: Self = Self::new(ZERO, ZERO, ZERO, ONE);
+
+  pub const RGB: Self = Self::new(ONE, ONE, ONE, ZERO);
+  pub const RGBW: Self = Self::new(ONE, ONE, ONE, ONE);
+  pub const NONE: Self = Self::new(ZERO, ZERO, ZERO, ZERO);
+
+  pub const YELLOW: Self = Self::new(ONE, ONE, ZERO, ZERO);
+  pub const MAGENTA: Self = Self::new(ONE, ZERO, ONE, ZERO);
+  pub const CYAN: Self = Self::new(ZERO, ONE, ONE, ZERO);
+
+  /// Blackbody colors from 1000K to 10000K in steps of 500K, for `from_kelvin`.
+  #[rustfmt::skip]
+  const KELVIN_TABLE: [[u8; 3]; 19] = [
+    [255,  56,   0], [255, 109,   0], [255, 137,  18], [255, 161,  72],
+    [255, 180, 107], [255, 196, 137], [255, 209, 163], [255, 219, 186],
+    [255, 228, 206], [255, 236, 224], [255, 243, 239], [255, 249, 253],
+    [245, 243, 255], [235, 238, 255], [227, 233, 255], [220, 229, 255],
+    [214, 225, 255], [208, 222, 255], [204, 219, 255],
+  ];
+
+  pub const STANDARD_PALETTE: [NormRgbw; 9] = [
+    NormRgbw::RED,
+    NormRgbw::GREEN,
+    NormRgbw::BLUE,
+    NormRgbw::WHITE,
+    NormRgbw::RGB,
+    NormRgbw::RGBW,
+    NormRgbw::YELLOW,
+    NormRgbw::MAGENTA,
+    NormRgbw::CYAN,
+  ];
+}
+
+impl core::ops::Index<usize> for NormRgbw {
+  type Output = Fix32;
+
+  fn index(&self, index: usize) -> &Self::Output {
+    match index {
+      0 => &self.r,
+      1 => &self.g,
+      2 => &self.b,
+      3 => &self.w,
+      _ => panic!("Out of range"),
+    }
+  }
+}
+impl core::ops::IndexMut<usize> for NormRgbw {
+  fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+    match index {
+      0 => &mut self.r,
+      1 => &mut self.g,
+      2 => &mut self.b,
+      3 => &mut self.w,
+      _ => panic!("Out of range"),
+    }
+  }
+}
+
+pub fn normalize([r, g, b, w]: [u8; 4]) -> [Fix32; 4] {
+  [
+    nl!(r) / nl!(255u8),
+    nl!(g) / nl!(255u8),
+    nl!(b) / nl!(255u8),
+    nl!(w) / nl!(255u8),
+  ]
+}
+
+pub fn denormalize([r, g, b, w]: [Fix32; 4]) -> [u8; 4] {
+  [
+    (r * nl!(255u8)).to_num(),
+    (g * nl!(255u8)).to_num(),
+    (b * nl!(255u8)).to_num(),
+    (w * nl!(255u8)).to_num(),
+  ]
+}
+
+/// Like `denormalize`, but carries the rounding error of every channel over to the next frame,
+/// so values between the 8-bit steps are displayed on average over time.
+pub fn denormalize_dithered(channels: [Fix32; 4], error: &mut [Fix32; 4]) -> [u8; 4] {
+  let mut raw = [0; 4];
+  for ((raw, c), error) in raw.iter_mut().zip(channels).zip(error) {
+    let value = (c * nl!(255u8) + *error).clamp(ZERO, nl!(255u8));
+    let floor = value.floor();
+    *error = value - floor;
+    *raw = floor.to_num();
+  }
+  raw
+}
+
+/// Packs the channels in the given order, starting at the most significant byte.
+/// Channels which aren't part of the order are dropped.
+pub fn pack(channels: [u8; 4], order: ChannelOrder) -> u32 {
+  let mut word = 0u32;
+  for (i, &c) in order.channels().iter().enumerate() {
+    word |= (channels[c] as u32) << (24 - 8 * i);
+  }
+  word
+}
+
+pub fn unpack(word: u32, order: ChannelOrder) -> [u8; 4] {
+  let mut channels = [0u8; 4];
+  for (i, &c) in order.channels().iter().enumerate() {
+    channels[c] = (word >> (24 - 8 * i)) as u8;
+  }
+  channels
+}
+
+impl rand::distributions::Distribution<NormHsv> for rand::distributions::Standard {
+  fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> NormHsv {
+    let hue = nl!(rng.gen::<f32>());
+    NormHsv::new(hue, ONE, ONE)
+  }
+}
+
+impl NormRgbw {
+  /// Brightness `b` between 0.0 and 1.0
+  #[must_use]
+  pub fn brightness(self, mut b: Fix32) -> Self {
+    b = (cordic::exp(b) - nl!(1)) / (nl!(fixed::consts::E) - nl!(1));
+    self.scale(b)
+  }
+
+  #[must_use]
+  pub fn scale(self, scalar: Fix32) -> Self {
+    NormRgbw::new(
+      scalar * self.r,
+      scalar * self.g,
+      scalar * self.b,
+      scalar * self.w,
+    )
+  }
+
+  #[must_use]
+  pub fn mix(self, other: Self) -> Self {
+    self.gradient(other, ONE / nl!(2))
+  }
+
+  #[must_use]
+  pub fn gradient(self, other: Self, t: Fix32) -> Self {
+    NormRgbw::new(
+      (ONE - t) * self.r + t * other.r,
+      (ONE - t) * self.g + t * other.g,
+      (ONE - t) * self.b + t * other.b,
+      (ONE - t) * self.w + t * other.w,
+    )
+  }
+
+  /// Interpolates the RGB part in OKLab, so the steps look even.
+  /// The w channel is interpolated linearly.
+  #[must_use]
+  pub fn perceptual_gradient(self, other: Self, t: Fix32) -> Self {
+    // the round trip through OKLab isn't exact
+    if t == ZERO {
+      return self;
+    } else if t == ONE {
+      return other;
+    }
+    let lab = OkLab::from(self).gradient(other.into(), t);
+    NormRgbw {
+      w: (ONE - t) * self.w + t * other.w,
+      ..lab.into()
+    }
+  }
+
+  /// White of the color temperature `kelvin`, clamped to 1000K..=10000K.
+  /// The white common to all channels is displayed by the w channel.
+  pub fn from_kelvin(kelvin: u16) -> Self {
+    let kelvin = kelvin.clamp(1000, 10000) - 1000;
+    let i = (kelvin / 500) as usize;
+    let t = nl!(kelvin % 500) / nl!(500);
+    let from = NormRgbw::from_kelvin_table(i);
+    let to = NormRgbw::from_kelvin_table((i + 1).min(Self::KELVIN_TABLE.len() - 1));
+    from.gradient(to, t).extract_white(NormRgbw::RGB)
+  }
+
+  fn from_kelvin_table(i: usize) -> Self {
+    let [r, g, b] = Self::KELVIN_TABLE[i];
+    Self::from_u8_channel_array([r, g, b, 0])
+  }
+
+  /// Moves the white contained in the RGB part to the w channel,
+  /// where `led` is the color of the white LED expressed in RGB.
+  /// The w channel is limited to 1, the rest stays in RGB.
+  /// A black `led` can't show any of the RGB part and leaves the color as it is.
+  #[must_use]
+  pub fn extract_white(self, led: NormRgbw) -> Self {
+    if [led.r, led.g, led.b].iter().all(|&l| l <= ZERO) {
+      return self;
+    }
+    let mut amount = ONE - self.w;
+    for (c, l) in [(self.r, led.r), (self.g, led.g), (self.b, led.b)] {
+      if l > ZERO && c < amount * l {
+        amount = c / l;
+      }
+    }
+    let amount = amount.max(ZERO);
+    NormRgbw::new(
+      self.r - amount * led.r,
+      self.g - amount * led.g,
+      self.b - amount * led.b,
+      self.w + amount,
+    )
+  }
+}
+
+/// How the w channel is used by strips with a white LED.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhiteExtraction {
+  /// Colors are displayed as they are.
+  #[default]
+  Off,
+  /// White is extracted from the RGB part, see `NormRgbw::extract_white`.
+  /// Holds the color of the white LED, so its color temperature can be accounted for.
+  On(NormRgbw),
+}
+
+impl WhiteExtraction {
+  /// A white LED which looks like full RGB.
+  pub const NEUTRAL: Self = WhiteExtraction::On(NormRgbw::RGB);
+
+  #[must_use]
+  pub fn apply(self, color: NormRgbw) -> NormRgbw {
+    match self {
+      WhiteExtraction::Off => color,
+      WhiteExtraction::On(led) => color.extract_white(led),
+    }
+  }
+}
+
+/// How a color is combined with the color below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+  /// Covers the color below.
+  #[default]
+  Normal,
+  Add,
+  Multiply,
+  /// Inverse of multiplying the inverses, brightens like `Add` without saturating as fast.
+  Screen,
+  /// The brighter value of every channel.
+  Max,
+}
+
+impl NormRgbw {
+  /// Combines `top` with this color, the result is mixed in by `opacity`.
+  /// Channels are clamped to `[0, 1]`.
+  #[must_use]
+  pub fn blend(self, top: Self, mode: BlendMode, opacity: Fix32) -> Self {
+    let mut channels = self.into_channel_array();
+    for (c, top) in channels.iter_mut().zip(top.into_channel_array()) {
+      let (b, t) = ((*c).clamp(ZERO, ONE), top.clamp(ZERO, ONE));
+      let blended = match mode {
+        BlendMode::Normal => t,
+        BlendMode::Add => (b + t).min(ONE),
+        BlendMode::Multiply => b * t,
+        BlendMode::Screen => ONE - (ONE - b) * (ONE - t),
+        BlendMode::Max => b.max(t),
+      };
+      *c = b + opacity * (blended - b);
+    }
+    Self::from_channel_array(channels)
+  }
+}
+
+impl core::ops::Add for NormRgbw {
+  type Output = NormRgbw;
+  fn add(self, other: Self) -> Self {
+    NormRgbw::new(
+      self.r + other.r,
+      self.g + other.g,
+      self.b + other.b,
+      self.w + other.w,
+    )
+  }
+}
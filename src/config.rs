@@ -1,18 +1,88 @@
 use alloc::boxed::Box;
-use arclib::{Fix32, ONE};
+use arclib::{
+  calibration::Calibration,
+  control::{KeyMap, ShowSpec},
+  geometry::Geometry,
+  output::OutputSettings,
+  power::PowerLimit,
+  settings::Settings,
+  Fix32, ONE,
+};
 
-use crate::show::Show;
+use crate::show::{Memory, Show, Transition};
 
 pub struct Config {
   pub show: Option<Box<dyn Show + Send>>,
+  /// How the last selected show was created, so it can be restored after a restart.
+  pub show_spec: Option<ShowSpec>,
   pub brightness: Fix32,
+  /// Applied to every frame, see `arclib::output`.
+  pub calibration: Calibration,
+  /// Applied to every frame after the calibration.
+  pub power: PowerLimit,
+  /// Temporal dithering of the output, for more than 8 bits at low brightness.
+  pub dithering: bool,
+  /// Used when the show is replaced.
+  pub transition: Transition,
+  /// Number of lights of all outputs together.
+  pub nlights: usize,
+  /// Positions of the lights, passed to the shows.
+  pub geometry: Geometry,
+  /// Set when `geometry` changed, so the show task updates its copy.
+  pub geometry_changed: bool,
+  /// Keys accepted over UART.
+  pub keys: KeyMap,
+  /// Values the shows keep across restarts, updated by the show task.
+  pub memory: Memory,
 }
 
 impl Default for Config {
   fn default() -> Self {
     Self {
       show: None,
+      show_spec: None,
       brightness: ONE,
+      calibration: Calibration::default(),
+      power: PowerLimit::default(),
+      dithering: true,
+      transition: Transition::default(),
+      nlights: 0,
+      geometry: Geometry::default(),
+      geometry_changed: true,
+      keys: KeyMap::default(),
+      memory: Memory::default(),
     }
   }
 }
+
+impl Config {
+  /// The part of the config which is applied to every frame.
+  pub fn output(&self) -> OutputSettings {
+    OutputSettings {
+      brightness: self.brightness,
+      calibration: self.calibration,
+      power: self.power,
+      dithering: self.dithering,
+    }
+  }
+
+  /// The part of the config which is stored in flash.
+  pub fn settings(&self) -> Settings {
+    Settings {
+      brightness: self.brightness,
+      show: self.show_spec.clone(),
+      calibration: self.calibration,
+      keys: self.keys.clone(),
+      memory: self.memory.clone(),
+    }
+  }
+
+  pub fn restore(&mut self, settings: &Settings) {
+    self.brightness = settings.brightness;
+    self.calibration = settings.calibration;
+    self.keys = settings.keys.clone();
+    self.memory = settings.memory.clone();
+    self.show = settings.show.as_ref().and_then(|spec| spec.create().ok());
+    self.show_spec = settings.show.clone();
+  }
+}
@@ -0,0 +1,452 @@
+//! Hardware independent handling of remote input.
+
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{fmt, str::FromStr};
+use infrared::remotecontrol::Action;
+
+use crate::{
+  color::{BlendMode, NormRgbw},
+  nl,
+  segment::Segment,
+  show::{self, Show},
+  Fix32, ONE,
+};
+
+/// What should happen in reaction to a remote action.
+pub enum Command {
+  /// Stop the current show.
+  Stop,
+  /// Replace the current show.
+  SetShow(ShowSpec),
+  SetBrightness(Fix32),
+  ChangeBrightness(Fix32),
+  /// The action isn't handled here and should be forwarded to the current show.
+  Forward(Action),
+}
+
+#[derive(Default)]
+pub enum InputState {
+  #[default]
+  Waiting,
+  ShowSelection,
+  BrightnessSelection,
+}
+
+impl InputState {
+  pub fn handle(&mut self, action: Action) -> Option<Command> {
+    match self {
+      InputState::Waiting => match action {
+        Action::Stop => Some(Command::Stop),
+        Action::Play_Pause => {
+          *self = InputState::ShowSelection;
+          None
+        }
+        Action::Time => {
+          *self = InputState::BrightnessSelection;
+          None
+        }
+        Action::Rewind => Some(Command::ChangeBrightness(nl!(-0.05))),
+        Action::Forward => Some(Command::ChangeBrightness(nl!(0.05))),
+        _ => Some(Command::Forward(action)),
+      },
+      InputState::ShowSelection => next_show(action).map(|next_show| {
+        *self = InputState::Waiting;
+        Command::SetShow(next_show)
+      }),
+      InputState::BrightnessSelection => number_from_action(action).map(|brightness| {
+        *self = InputState::Waiting;
+        Command::SetBrightness(nl!(brightness) / nl!(9))
+      }),
+    }
+  }
+}
+
+/// Keys accepted over serial connections, unless they are remapped.
+#[rustfmt::skip]
+pub const DEFAULT_KEYS: &[(char, Action)] = &[
+  ('1', Action::One),
+  ('2', Action::Two),
+  ('3', Action::Three),
+  ('4', Action::Four),
+  ('5', Action::Five),
+  ('6', Action::Six),
+  ('7', Action::Seven),
+  ('8', Action::Eight),
+  ('9', Action::Nine),
+  ('0', Action::Zero),
+  ('s', Action::Stop),
+  ('p', Action::Play_Pause),
+  ('t', Action::Time),
+  ('x', Action::Teletext),
+  ('r', Action::Repeat),
+  ('?', Action::Random),
+  ('g', Action::Prog),
+  ('<', Action::Prev),
+  ('>', Action::Next),
+  ('[', Action::Rewind),
+  (']', Action::Forward),
+];
+
+/// The actions keys can be mapped to, by name.
+/// The position in the list is used when storing key mappings, so only append to it.
+#[rustfmt::skip]
+pub const ACTIONS: &[(&str, Action)] = &[
+  ("one",       Action::One),
+  ("two",       Action::Two),
+  ("three",     Action::Three),
+  ("four",      Action::Four),
+  ("five",      Action::Five),
+  ("six",       Action::Six),
+  ("seven",     Action::Seven),
+  ("eight",     Action::Eight),
+  ("nine",      Action::Nine),
+  ("zero",      Action::Zero),
+  ("stop",      Action::Stop),
+  ("play",      Action::Play_Pause),
+  ("time",      Action::Time),
+  ("teletext",  Action::Teletext),
+  ("repeat",    Action::Repeat),
+  ("random",    Action::Random),
+  ("prog",      Action::Prog),
+  ("prev",      Action::Prev),
+  ("next",      Action::Next),
+  ("rewind",    Action::Rewind),
+  ("forward",   Action::Forward),
+];
+
+pub fn action_name(action: Action) -> Option<&'static str> {
+  ACTIONS
+    .iter()
+    .find(|(_, a)| *a == action)
+    .map(|(name, _)| *name)
+}
+
+pub fn action_from_name(name: &str) -> Option<Action> {
+  ACTIONS
+    .iter()
+    .find(|(n, _)| *n == name)
+    .map(|(_, action)| *action)
+}
+
+/// Maps the keys accepted over serial connections to remote actions, using the default mapping.
+pub fn action_from_key(key: &str) -> Option<Action> {
+  let key = single_char(key)?;
+  DEFAULT_KEYS
+    .iter()
+    .find(|(k, _)| *k == key)
+    .map(|(_, action)| *action)
+}
+
+fn single_char(key: &str) -> Option<char> {
+  let mut chars = key.chars();
+  match (chars.next(), chars.next()) {
+    (Some(c), None) => Some(c),
+    _ => None,
+  }
+}
+
+/// Maps the keys accepted over serial connections to remote actions.
+/// Starts with `DEFAULT_KEYS` and can be changed at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+  keys: Vec<(char, Action)>,
+}
+
+impl Default for KeyMap {
+  fn default() -> Self {
+    Self {
+      keys: DEFAULT_KEYS.to_vec(),
+    }
+  }
+}
+
+impl KeyMap {
+  pub fn new(keys: Vec<(char, Action)>) -> Self {
+    Self { keys }
+  }
+
+  pub fn keys(&self) -> &[(char, Action)] {
+    &self.keys
+  }
+
+  pub fn action(&self, key: &str) -> Option<Action> {
+    let key = single_char(key)?;
+    self
+      .keys
+      .iter()
+      .find(|(k, _)| *k == key)
+      .map(|(_, action)| *action)
+  }
+
+  /// Maps `key` to `action`, `None` removes the mapping.
+  pub fn set(&mut self, key: char, action: Option<Action>) {
+    self.keys.retain(|(k, _)| *k != key);
+    if let Some(action) = action {
+      self.keys.push((key, action));
+    }
+  }
+
+  /// Changes the mapping, given as `<key> <action|none>` or `reset`.
+  pub fn update(&mut self, setting: &str) -> Result<(), &'static str> {
+    let mut words = setting.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+      (Some("reset"), None, None) => *self = Self::default(),
+      (Some(key), Some(action), None) => {
+        let key = single_char(key).ok_or("expected a single character as key")?;
+        let action = match action {
+          "none" => None,
+          name => Some(action_from_name(name).ok_or("unknown action")?),
+        };
+        self.set(key, action);
+      }
+      _ => return Err("expected `<key> <action|none>` or `reset`"),
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for KeyMap {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, (key, action)) in self.keys.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+      write!(f, "{}={}", key, action_name(*action).unwrap_or("?"))?;
+    }
+    Ok(())
+  }
+}
+
+#[rustfmt::skip]
+pub fn number_from_action(action: Action) -> Option<usize> {
+  match action {
+    Action::Zero  => Some(0),
+    Action::One   => Some(1),
+    Action::Two   => Some(2),
+    Action::Three => Some(3),
+    Action::Four  => Some(4),
+    Action::Five  => Some(5),
+    Action::Six   => Some(6),
+    Action::Seven => Some(7),
+    Action::Eight => Some(8),
+    Action::Nine  => Some(9),
+    _ => None,
+  }
+}
+
+pub fn color_from_action(action: Action) -> Option<NormRgbw> {
+  number_from_action(action).map(|i| {
+    if i == 0 {
+      NormRgbw::NONE
+    } else {
+      NormRgbw::STANDARD_PALETTE[i - 1]
+    }
+  })
+}
+
+macro_rules! show {
+  ($s:expr) => {
+    Some(Box::new($s))
+  };
+}
+
+/// A show given by name and `key=value` parameters, e.g. `white kelvin=3000`.
+/// Unlike the show itself it can be stored and used to create the show again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShowSpec(String);
+
+impl ShowSpec {
+  pub fn new(spec: &str) -> Self {
+    Self(spec.split_whitespace().collect::<Vec<_>>().join(" "))
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  pub fn name(&self) -> &str {
+    self.0.split(' ').next().unwrap_or_default()
+  }
+
+  /// Value of the parameter `key`, `None` if it isn't given.
+  pub fn param(&self, key: &str) -> Option<&str> {
+    self
+      .0
+      .split(' ')
+      .skip(1)
+      .find_map(|param| param.strip_prefix(key)?.strip_prefix('='))
+  }
+
+  /// Value of the parameter `key`, `default` if it isn't given.
+  fn parse_param<T: FromStr>(
+    &self,
+    key: &str,
+    default: T,
+    error: &'static str,
+  ) -> Result<T, &'static str> {
+    match self.param(key) {
+      Some(value) => value.parse().map_err(|_| error),
+      None => Ok(default),
+    }
+  }
+
+  pub fn create(&self) -> Result<Box<dyn Show + Send>, &'static str> {
+    match self.name() {
+      "uniform" => {
+        let color = match self.param("color") {
+          Some(hex) => NormRgbw::from_hex(hex).ok_or("invalid color")?,
+          None => NormRgbw::WHITE,
+        };
+        Ok(Box::new(show::UniformShow::new(color)))
+      }
+      "white" => {
+        let kelvin = self.parse_param("kelvin", 2700, "invalid kelvin")?;
+        let brightness = self.parse_param("brightness", ONE, "invalid brightness")?;
+        Ok(Box::new(show::WhiteShow::new(kelvin, brightness)))
+      }
+      "fire" => {
+        let cooling = self.parse_param("cooling", nl!(0.55), "invalid cooling")?;
+        let sparking = self.parse_param("sparking", nl!(0.47), "invalid sparking")?;
+        let palette =
+          self.parse_param("palette", show::FirePalette::default(), "invalid palette")?;
+        Ok(Box::new(show::FireShow::new(cooling, sparking, palette)))
+      }
+      "rainbow" => {
+        let speed = self.parse_param("speed", ONE, "invalid speed")?;
+        Ok(Box::new(show::RainbowShow::new(speed)))
+      }
+      name @ ("lava" | "aurora" | "ocean" | "pastel") => {
+        let style = name.parse().map_err(|_| "unknown show")?;
+        let speed = self.parse_param("speed", ONE, "invalid speed")?;
+        let scale = self.parse_param("scale", ONE, "invalid scale")?;
+        Ok(Box::new(show::AmbientShow::new(style, speed, scale)))
+      }
+      "balls" => {
+        let count = self.parse_param("count", 3, "invalid count")?;
+        Ok(Box::new(show::BouncingBallsShow::new(count)))
+      }
+      "comets" => {
+        let count = self.parse_param("count", 3, "invalid count")?;
+        let tail = self.parse_param("tail", nl!(0.5), "invalid tail")?;
+        Ok(Box::new(show::CometShow::new(count, tail)))
+      }
+      "twinkle" => {
+        let density = self.parse_param("density", nl!(0.1), "invalid density")?;
+        let fade = self.parse_param("fade", ONE, "invalid fade")?;
+        let background = match self.param("background") {
+          Some(hex) => NormRgbw::from_hex(hex).ok_or("invalid background")?,
+          None => NormRgbw::NONE,
+        };
+        let palette = self.parse_param(
+          "palette",
+          show::TwinklePalette::default(),
+          "invalid palette",
+        )?;
+        Ok(Box::new(show::TwinkleShow::new(
+          density, fade, background, palette,
+        )))
+      }
+      name => named_show(name).ok_or("unknown show"),
+    }
+  }
+}
+
+impl fmt::Display for ShowSpec {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+#[rustfmt::skip]
+pub fn next_show(action: Action) -> Option<ShowSpec> {
+  if let Some(color) = color_from_action(action) {
+    return Some(ShowSpec::new(&format!("uniform color=#{:x}", color)));
+  }
+  let name = match action {
+    Action::Stop       => "null",
+    Action::Prog       => "demo",
+    Action::Time       => "clock",
+    Action::Random     => "random",
+    Action::Teletext   => "snake",
+    Action::Repeat     => "spotlight",
+    Action::Play_Pause => "white",
+    _ => return None,
+  };
+  Some(ShowSpec::new(name))
+}
+
+/// Names accepted by `show_from_name`.
+pub const SHOW_NAMES: &[&str] = &[
+  "null",
+  "quick",
+  "demo",
+  "rainbow",
+  "fire",
+  "twinkle",
+  "lava",
+  "aurora",
+  "ocean",
+  "pastel",
+  "balls",
+  "fireworks",
+  "comets",
+  "gradient",
+  "layers",
+  "clock",
+  "rgb-clock",
+  "random",
+  "snake",
+  "spotlight",
+  "sweep",
+  "uniform",
+  "white",
+  "zones",
+];
+
+/// Creates a show from its name, optionally followed by parameters, see `ShowSpec`.
+pub fn show_from_name(spec: &str) -> Option<Box<dyn Show + Send>> {
+  ShowSpec::new(spec).create().ok()
+}
+
+#[rustfmt::skip]
+fn named_show(name: &str) -> Option<Box<dyn Show + Send>> {
+  match name {
+    "null"      => show!(show::NullShow),
+    "quick"     => show!(show::QuickShow),
+    "demo"      => show!(show::DemoShow::default()),
+    "fireworks" => show!(show::FireworksShow::default()),
+    "gradient"  => show!(show::GradientShow::new(NormRgbw::RED, NormRgbw::YELLOW)),
+    "layers"    => show!(layers_show()),
+    "clock"     => show!(show::SeparatedClockShow),
+    "rgb-clock" => show!(show::RgbClockShow::default()),
+    "random"    => show!(show::RandomShow::default()),
+    "snake"     => show!(show::SnakeShow::default()),
+    "spotlight" => show!(show::SpotlightShow::default()),
+    "sweep"     => show!(show::SweepShow::default()),
+    "zones"     => show!(zones_show()),
+    _ => None,
+  }
+}
+
+/// The clock over a dimmed gradient.
+fn layers_show() -> show::LayersShow {
+  let background = show::GradientShow::new(NormRgbw::BLUE, NormRgbw::CYAN);
+  show::LayersShow::new(vec![
+    show::Layer::new(Box::new(background), BlendMode::Normal, nl!(0.2)),
+    show::Layer::new(Box::new(show::SeparatedClockShow), BlendMode::Max, ONE),
+  ])
+}
+
+/// The clock on the first half of the strip and a uniform color on the other one.
+fn zones_show() -> show::ZonesShow {
+  show::ZonesShow::with_layout(
+    |len| {
+      let clock = Segment::new("clock", 0, len / 2);
+      vec![clock, clock.next("light", len - len / 2)]
+    },
+    vec![
+      Box::new(show::SeparatedClockShow),
+      Box::new(show::UniformShow::new(NormRgbw::WHITE)),
+    ],
+  )
+}
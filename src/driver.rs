@@ -0,0 +1,225 @@
+use alloc::{boxed::Box, vec::Vec};
+use arclib::{
+  color::RawChannel,
+  geometry::Geometry,
+  show::{FrameContext, Memory, Micros, Show, FRAME_TIME},
+};
+use rp_pico::{
+  hal::{self, clocks::ClockSource},
+  pac,
+};
+use rtic::Mutex;
+
+use crate::{
+  app::{
+    self, monotonics,
+    show_task::{self, SharedResources},
+  },
+  light::{controller::ColorMemoryController, Lights, OutputConfig},
+  storage::save_settings,
+  uprintln,
+};
+
+type Instant = <app::Monotonic as rtic::Monotonic>::Instant;
+type Duration = <app::Monotonic as rtic::Monotonic>::Duration;
+
+/// How often to check for a new show, while none is running.
+const IDLE_TIME: Micros = 10_000;
+
+/// The show continues when no frame was streamed for this long.
+const STREAM_TIMEOUT: Micros = 5_000_000;
+
+struct RunningShow {
+  show: Box<dyn Show + Send>,
+  start: Instant,
+  last_frame: Instant,
+  /// When the show wants to render its next frame.
+  next_frame: Instant,
+}
+
+pub struct ShowTask {
+  ctrl: ColorMemoryController,
+  running: Option<RunningShow>,
+  /// Copy of the geometry in the config, so it isn't locked while rendering.
+  geometry: Geometry,
+  /// Swapped with the one of `StreamedFrame`, so it isn't locked while displaying.
+  streamed: Vec<[RawChannel; 4]>,
+  streaming: bool,
+  /// Copy of the memory in the config, so it isn't locked while rendering.
+  memory: Memory,
+}
+
+impl ShowTask {
+  pub fn init(
+    outputs: &[OutputConfig],
+    pio0: pac::PIO0,
+    pio1: pac::PIO1,
+    dma: pac::DMA,
+    sys_clock: &hal::clocks::SystemClock,
+    resets: &mut pac::RESETS,
+  ) -> Self {
+    let sys_freq = sys_clock.get_freq().0 as f32;
+    let lights = Lights::init(outputs, pio0, pio1, dma, resets, sys_freq);
+    let ctrl = ColorMemoryController::new(lights);
+
+    show_task::spawn().unwrap();
+
+    Self {
+      ctrl,
+      running: None,
+      geometry: Geometry::default(),
+      streamed: Vec::new(),
+      streaming: false,
+      memory: Memory::default(),
+    }
+  }
+}
+
+/// Drives the current show.
+/// Renders and displays a single frame and then reschedules itself.
+/// With dithering the frame is displayed again every `FRAME_TIME` in between.
+/// While frames are streamed, they are displayed instead and the show is paused.
+pub fn show_task(ctx: show_task::Context) {
+  let ShowTask {
+    ctrl,
+    running,
+    geometry,
+    streamed,
+    streaming,
+    memory,
+  } = ctx.local.show_task;
+  let SharedResources {
+    mut show_cancellation_token,
+    mut config,
+    mut remote_input,
+    mut streamed_frame,
+  } = ctx.shared;
+
+  let now = monotonics::now();
+
+  let (active, pending) = streamed_frame.lock(|frame| {
+    let active = match frame.received {
+      Some(received) => (now - received).to_micros() < STREAM_TIMEOUT,
+      None => false,
+    };
+    let pending = core::mem::take(&mut frame.pending);
+    if pending {
+      core::mem::swap(&mut frame.colors, streamed);
+    }
+    (active, pending)
+  });
+  if active != *streaming {
+    *streaming = active;
+    if active {
+      uprintln!("streaming started");
+    } else {
+      uprintln!("streaming stopped");
+    }
+  }
+  if active {
+    if pending {
+      // the computer takes care of brightness and gamma, but the strip still has to be protected
+      let power = config.lock(|config| config.power);
+      power.limit(streamed);
+      ctrl.raw().set_display(streamed);
+    }
+    show_task::spawn_at(now + Duration::micros(FRAME_TIME)).unwrap();
+    return;
+  }
+
+  if show_cancellation_token.lock(|token| token.is_requested()) {
+    show_cancellation_token.lock(|token| token.reset());
+    *running = None;
+  }
+  config.lock(|config| {
+    if config.geometry_changed {
+      config.geometry_changed = false;
+      geometry.clone_from(&config.geometry);
+    }
+  });
+  let next_show = config.lock(|config| {
+    let transition = config.transition;
+    let show = config.show.take()?;
+    memory.clone_from(&config.memory);
+    Some(transition.apply(show))
+  });
+  if let Some(show) = next_show {
+    *running = Some(RunningShow {
+      show,
+      start: now,
+      last_frame: now,
+      next_frame: now,
+    });
+  }
+
+  let next_wakeup = match running {
+    Some(running) => {
+      if now >= running.next_frame {
+        let ctx = FrameContext {
+          time: (now - running.start).to_micros(),
+          delta: (now - running.last_frame).to_micros(),
+          action: remote_input.lock(|input| input.0.take()),
+          seed: running.start.ticks(),
+          geometry,
+          memory,
+        };
+        running.last_frame = now;
+
+        let next_frame = running.show.render(&ctx, ctrl.memory_mut());
+        running.next_frame = now + Duration::micros(next_frame);
+
+        if memory.take_changed() {
+          config.lock(|config| config.memory.clone_from(memory));
+          save_settings();
+        }
+      }
+      ctrl.display(&mut config);
+
+      if config.lock(|config| config.dithering) {
+        running.next_frame.min(now + Duration::micros(FRAME_TIME))
+      } else {
+        running.next_frame
+      }
+    }
+    None => now + Duration::micros(IDLE_TIME),
+  };
+  show_task::spawn_at(next_wakeup).unwrap();
+}
+
+/// The latest frame streamed from a computer, see `arclib::stream`.
+#[derive(Default)]
+pub struct StreamedFrame {
+  colors: Vec<[RawChannel; 4]>,
+  received: Option<Instant>,
+  /// It wasn't displayed yet.
+  pending: bool,
+}
+
+impl StreamedFrame {
+  /// Replaces the frame with RGB for every light.
+  pub fn set(&mut self, rgb: &[u8], now: Instant) {
+    self.colors.clear();
+    self
+      .colors
+      .extend(rgb.chunks_exact(3).map(|c| [c[0], c[1], c[2], 0]));
+    self.received = Some(now);
+    self.pending = true;
+  }
+}
+
+#[derive(Default)]
+pub struct ShowCancellationToken(bool);
+
+impl ShowCancellationToken {
+  pub fn is_requested(&self) -> bool {
+    self.0
+  }
+
+  pub fn request(&mut self) {
+    self.0 = true;
+  }
+
+  fn reset(&mut self) {
+    self.0 = false;
+  }
+}
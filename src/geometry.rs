@@ -0,0 +1,270 @@
+//! Positions of the lights in space.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::{color::NormRgbw, nl, segment::Segment, Fix32, ONE, ZERO};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Point {
+  pub x: Fix32,
+  pub y: Fix32,
+  pub z: Fix32,
+}
+
+impl Point {
+  pub const fn new(x: Fix32, y: Fix32, z: Fix32) -> Self {
+    Self { x, y, z }
+  }
+
+  pub const fn new_2d(x: Fix32, y: Fix32) -> Self {
+    Self::new(x, y, ZERO)
+  }
+
+  /// Saturates for far away points.
+  pub fn distance_squared(self, other: Self) -> Fix32 {
+    [self.x - other.x, self.y - other.y, self.z - other.z]
+      .iter()
+      .map(|d| d.saturating_mul(*d))
+      .fold(ZERO, Fix32::saturating_add)
+  }
+
+  pub fn distance(self, other: Self) -> Fix32 {
+    cordic::sqrt(self.distance_squared(other))
+  }
+}
+
+/// Lights on a grid, addressed by column and row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matrix {
+  pub width: usize,
+  pub height: usize,
+  /// Every other row runs backwards, like strips are usually laid out.
+  pub serpentine: bool,
+}
+
+impl Matrix {
+  pub const fn new(width: usize, height: usize, serpentine: bool) -> Self {
+    Self {
+      width,
+      height,
+      serpentine,
+    }
+  }
+
+  /// Index of the light at column `x` and row `y`.
+  pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    let x = if self.serpentine && y % 2 == 1 {
+      self.width - 1 - x
+    } else {
+      x
+    };
+    Some(y * self.width + x)
+  }
+
+  pub fn set_xy(&self, frame: &mut [NormRgbw], x: usize, y: usize, color: NormRgbw) {
+    if let Some(led) = self.index(x, y).and_then(|i| frame.get_mut(i)) {
+      *led = color;
+    }
+  }
+
+  /// Positions between 0 and 1, with `y` pointing down the rows.
+  pub fn geometry(&self) -> Geometry {
+    let mut points = vec![Point::default(); self.width * self.height];
+    for y in 0..self.height {
+      for x in 0..self.width {
+        let i = self.index(x, y).unwrap();
+        points[i] = Point::new_2d(fraction(x, self.width), fraction(y, self.height));
+      }
+    }
+    Geometry::new(points)
+  }
+}
+
+/// `i` of `n` evenly spaced between 0 and 1.
+fn fraction(i: usize, n: usize) -> Fix32 {
+  if n <= 1 {
+    ZERO
+  } else {
+    nl!(i) / nl!(n - 1)
+  }
+}
+
+/// Position of every light of the strip.
+/// The built-in layouts fit into the unit cube, loaded tables can use any unit.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Geometry {
+  points: Vec<Point>,
+}
+
+impl Geometry {
+  pub fn new(points: Vec<Point>) -> Self {
+    Self { points }
+  }
+
+  /// Lights in a straight line along the x axis.
+  pub fn linear(n: usize) -> Self {
+    Self::new(
+      (0..n)
+        .map(|i| Point::new_2d(fraction(i, n), ZERO))
+        .collect(),
+    )
+  }
+
+  /// Lights on a circle around `(0.5, 0.5)`, starting at the right and going counterclockwise.
+  pub fn ring(n: usize) -> Self {
+    let half = ONE / 2;
+    Self::new(
+      (0..n)
+        .map(|i| {
+          // angle in `[-pi, pi)`, which cordic can handle
+          let mut turn = nl!(i) / nl!(n.max(1));
+          if turn >= half {
+            turn -= ONE;
+          }
+          let (sin, cos) = cordic::sin_cos(turn * nl!(fixed::consts::TAU));
+          Point::new_2d(half + half * cos, half + half * sin)
+        })
+        .collect(),
+    )
+  }
+
+  pub fn len(&self) -> usize {
+    self.points.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.points.is_empty()
+  }
+
+  pub fn points(&self) -> &[Point] {
+    &self.points
+  }
+
+  /// Position of the `i`th light, `None` if it's unknown.
+  pub fn position(&self, i: usize) -> Option<Point> {
+    self.points.get(i).copied()
+  }
+
+  /// Sets the position of the `i`th light, unknown positions in between are set to the origin.
+  pub fn set_position(&mut self, i: usize, point: Point) {
+    if i >= self.points.len() {
+      self.points.resize(i + 1, Point::default());
+    }
+    self.points[i] = point;
+  }
+
+  /// The geometry of the lights of the segment, in the order of the segment.
+  pub fn segment(&self, segment: &Segment) -> Self {
+    Self::new(
+      (0..segment.len)
+        .map(|i| {
+          segment
+            .index(i)
+            .and_then(|i| self.position(i))
+            .unwrap_or_default()
+        })
+        .collect(),
+    )
+  }
+
+  /// Index of the light closest to `point`.
+  pub fn nearest(&self, point: Point) -> Option<usize> {
+    (0..self.points.len()).min_by_key(|&i| self.points[i].distance_squared(point))
+  }
+
+  /// Sets the light closest to the position.
+  pub fn set_xy(&self, frame: &mut [NormRgbw], x: Fix32, y: Fix32, color: NormRgbw) {
+    if let Some(led) = self
+      .nearest(Point::new_2d(x, y))
+      .and_then(|i| frame.get_mut(i))
+    {
+      *led = color;
+    }
+  }
+
+  /// Sets every light with a known position to the color of the effect at that position.
+  pub fn sample(&self, frame: &mut [NormRgbw], mut effect: impl FnMut(Point) -> NormRgbw) {
+    for (led, &point) in frame.iter_mut().zip(&self.points) {
+      *led = effect(point);
+    }
+  }
+
+  /// Changes the geometry, given as `linear <n>`, `ring <n>`, `matrix <width> <height> [serpentine]`
+  /// or `point <i> <x> <y> [z]` for loading a table light by light after `clear`.
+  /// Layouts with more than `nlights` lights and indices beyond them are rejected.
+  pub fn update(&mut self, setting: &str, nlights: usize) -> Result<(), &'static str> {
+    let mut words = setting.split_whitespace();
+    let name = words.next().ok_or("expected a layout or `point`")?;
+    let mut serpentine = false;
+    let mut values = [ZERO; 4];
+    let mut nvalues = 0;
+    for word in words {
+      if word == "serpentine" {
+        serpentine = true;
+        continue;
+      }
+      let value = values.get_mut(nvalues).ok_or("too many values")?;
+      *value = word.parse().map_err(|_| "invalid number")?;
+      nvalues += 1;
+    }
+    let count = |value: Fix32| {
+      if value < ZERO || value.frac() != ZERO {
+        Err("expected a count")
+      } else if value.to_num::<usize>() > nlights {
+        Err("more lights than the strip has")
+      } else {
+        Ok(value.to_num::<usize>())
+      }
+    };
+    let index = |value: Fix32| match count(value)? {
+      i if i < nlights => Ok(i),
+      _ => Err("index beyond the end of the strip"),
+    };
+
+    *self = match (name, nvalues) {
+      ("clear", 0) => Geometry::default(),
+      ("linear", 1) => Geometry::linear(count(values[0])?),
+      ("ring", 1) => Geometry::ring(count(values[0])?),
+      ("matrix", 2) => {
+        let (width, height) = (count(values[0])?, count(values[1])?);
+        match width.checked_mul(height) {
+          Some(n) if n <= nlights => Matrix::new(width, height, serpentine).geometry(),
+          _ => return Err("more lights than the strip has"),
+        }
+      }
+      ("point", 3 | 4) => {
+        let [i, x, y, z] = values;
+        self.set_position(index(i)?, Point::new(x, y, z));
+        return Ok(());
+      }
+      _ => {
+        return Err(
+          "expected `linear <n>`, `ring <n>`, `matrix <w> <h> [serpentine]`, `clear` or `point <i> <x> <y> [z]`",
+        )
+      }
+    };
+    Ok(())
+  }
+}
+
+impl fmt::Display for Geometry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} lights", self.len())?;
+    let first = self.points.first().copied().unwrap_or_default();
+    let (min, max) = self.points.iter().fold((first, first), |(min, max), p| {
+      (
+        Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+        Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+      )
+    });
+    write!(
+      f,
+      " from ({}, {}, {}) to ({}, {}, {})",
+      min.x, min.y, min.z, max.x, max.y, max.z
+    )
+  }
+}
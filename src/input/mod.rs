@@ -1,33 +1,31 @@
 pub mod remote;
+pub mod serial;
+pub mod shell;
 pub mod uart;
+pub mod usb;
 
-use alloc::boxed::Box;
-use arclib::nl;
-use infrared::remotecontrol::Action;
+use arclib::{
+  control::{Command, InputState},
+  ONE, ZERO,
+};
 use rtic::Mutex;
 
 use crate::{
   app::input_task::{self, SharedResources},
-  light::color::NormRgbw,
-  show::{self, Show},
+  config::Config,
+  driver::ShowCancellationToken,
+  storage::save_settings,
+  uprintln,
 };
 
+use self::remote::{Action, RemoteInput};
+
 #[derive(Default)]
 pub struct InputTask {
   state: InputState,
 }
-pub enum InputState {
-  Waiting,
-  ShowSelection,
-  BrightnessSelection,
-}
-impl Default for InputState {
-  fn default() -> Self {
-    Self::Waiting
-  }
-}
 
-pub fn input_task(ctx: input_task::Context) {
+pub fn input_task(ctx: input_task::Context, action: Action) {
   let InputTask { state } = ctx.local.input_task;
   let SharedResources {
     mut remote_input,
@@ -35,101 +33,53 @@ pub fn input_task(ctx: input_task::Context) {
     mut show_cancellation_token,
   } = ctx.shared;
 
-  let remote_action = remote_input
-    .lock(|input| input.0.take())
-    .expect("input_task should only be called when there is input");
-
-  match &state {
-    InputState::Waiting => match remote_action {
-      Action::Stop => {
-        show_cancellation_token.lock(|cancel| cancel.request());
-      }
-      Action::Play_Pause => {
-        *state = InputState::ShowSelection;
-      }
-      Action::Time => {
-        *state = InputState::BrightnessSelection;
-      }
-      Action::Rewind => {
-        config.lock(|config| config.brightness = (config.brightness - nl!(0.05)).max(nl!(0.0)));
-      }
-      Action::Forward => {
-        config.lock(|config| config.brightness = (config.brightness + nl!(0.05)).min(nl!(1.0)));
-      }
-      _ => {
-        remote_input.lock(|input| input.0 = Some(remote_action));
-      }
-    },
-    InputState::ShowSelection => {
-      if let Some(next_show) = next_show(remote_action) {
-        config.lock(|config| config.show = Some(next_show));
-        show_cancellation_token.lock(|cancel| cancel.request());
-        *state = InputState::Waiting;
-      }
-    }
-    InputState::BrightnessSelection => {
-      if let Some(brightness) = number_from_action(remote_action) {
-        let brightness = nl!(brightness) / nl!(9);
-        config.lock(|config| config.brightness = brightness);
-        *state = InputState::Waiting;
-      }
+  if let Some(command) = state.handle(action) {
+    let result = apply_command(
+      command,
+      &mut config,
+      &mut remote_input,
+      &mut show_cancellation_token,
+    );
+    if let Err(e) = result {
+      uprintln!("error: {}", e);
     }
   }
 }
 
-#[rustfmt::skip]
-fn number_from_action(action: Action) -> Option<usize> {
-  match action {
-    Action::Zero  => Some(0),
-    Action::One   => Some(1),
-    Action::Two   => Some(2),
-    Action::Three => Some(3),
-    Action::Four  => Some(4),
-    Action::Five  => Some(5),
-    Action::Six   => Some(6),
-    Action::Seven => Some(7),
-    Action::Eight => Some(8),
-    Action::Nine  => Some(9),
-    _ => None,
-  }
-}
-
-fn color_from_action(action: Action) -> Option<NormRgbw> {
-  number_from_action(action).map(|i| {
-    if i == 0 {
-      NormRgbw::NONE
-    } else {
-      NormRgbw::STANDARD_PALETTE[i - 1]
+/// Carries out a command, from the remote or the shell.
+/// Fails if the show can't be created.
+pub fn apply_command(
+  command: Command,
+  config: &mut impl Mutex<T = Config>,
+  remote_input: &mut impl Mutex<T = RemoteInput>,
+  show_cancellation_token: &mut impl Mutex<T = ShowCancellationToken>,
+) -> Result<(), &'static str> {
+  match command {
+    Command::Stop => {
+      config.lock(|config| config.show_spec = None);
+      show_cancellation_token.lock(|cancel| cancel.request());
+      save_settings();
     }
-  })
-}
-
-#[rustfmt::skip]
-fn next_show(action: Action) -> Option<Box<dyn Show + Send>> {
-  macro_rules! show {
-    ($s:expr) => {
-      Some(Box::new($s))
-    };
-  }
-
-  //const BYTES: &[u8] = &[0b1010_1010, 0b1111_1111, 0b0000_0000, 0b1100_1100];
-
-  if let Some(color) = color_from_action(action) {
-    show!(show::UniformShow::new(color))
-  } else {
-    match action {
-      Action::Stop       => show!(show::NullShow::default()),
-      Action::Prog       => show!(show::DemoShow::default()),
-      Action::Time       => show!(show::SeparatedClockShow::default()),
-      Action::Random     => show!(show::RandomShow::default()),
-      Action::Teletext   => show!(show::SnakeShow::default()),
-      //Action::Repeat     => show!(show::ByteShow::new(BYTES)),
-      Action::Repeat      => show!(show::SpotlightShow::default()),
-      Action::Prev       => None,
-      Action::Next       => None,
-      Action::Rewind     => None,
-      Action::Forward    => None,
-      _ => None,
+    Command::SetShow(spec) => {
+      let next_show = spec.create()?;
+      config.lock(|config| {
+        config.show = Some(next_show);
+        config.show_spec = Some(spec);
+      });
+      show_cancellation_token.lock(|cancel| cancel.request());
+      save_settings();
+    }
+    Command::SetBrightness(brightness) => {
+      config.lock(|config| config.brightness = brightness);
+      save_settings();
+    }
+    Command::ChangeBrightness(delta) => {
+      config.lock(|config| config.brightness = (config.brightness + delta).clamp(ZERO, ONE));
+      save_settings();
+    }
+    Command::Forward(action) => {
+      remote_input.lock(|input| input.0 = Some(action));
     }
   }
+  Ok(())
 }
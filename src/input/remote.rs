@@ -1,13 +1,9 @@
 use crate::{
-  app::{
-    self, input_task, monotonics,
-    remote_task::{self, SharedResources},
-  },
+  app::{self, input_task, monotonics, remote_task},
   uprintln,
 };
 use infrared::{self as ir, remotecontrol as irrc};
 use rp_pico::hal::gpio;
-use rtic::Mutex;
 
 pub type Action = irrc::Action;
 
@@ -39,16 +35,14 @@ impl RemoteTask {
 
 pub fn remote_task(ctx: remote_task::Context) {
   let RemoteTask { ir_receiver } = ctx.local.remote_task;
-  let SharedResources { mut remote_input } = ctx.shared;
 
   let now = monotonics::now();
   match ir_receiver.event_instant(now) {
     Ok(Some(cmd)) => match cmd.action() {
       Some(action) => {
-        remote_input.lock(|input| {
-          input.0 = Some(action);
-        });
-        input_task::spawn().unwrap();
+        if input_task::spawn(action).is_err() {
+          uprintln!("IR Receiver error: too many pending actions");
+        }
       }
       None => {}
     },
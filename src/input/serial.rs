@@ -0,0 +1,64 @@
+//! Input from serial connections, the same for the UART and USB.
+
+use core::fmt;
+
+use arclib::{
+  shell::LineBuffer,
+  stream::{Decoded, StreamDecoder},
+};
+use rtic::Mutex;
+
+use crate::{
+  app::monotonics,
+  config::Config,
+  driver::{ShowCancellationToken, StreamedFrame},
+  uprintln,
+};
+
+use super::{remote::RemoteInput, shell};
+
+/// Displays streamed frames and executes everything else line by line as shell commands.
+pub struct SerialInput {
+  line: LineBuffer,
+  stream: StreamDecoder,
+}
+
+impl SerialInput {
+  pub fn new(nlights: usize) -> Self {
+    Self {
+      line: LineBuffer::new(),
+      stream: StreamDecoder::new(nlights),
+    }
+  }
+
+  /// Handles received bytes, typed characters are echoed to `echo`.
+  pub fn handle(
+    &mut self,
+    bytes: &[u8],
+    echo: &mut impl fmt::Write,
+    config: &mut impl Mutex<T = Config>,
+    remote_input: &mut impl Mutex<T = RemoteInput>,
+    show_cancellation_token: &mut impl Mutex<T = ShowCancellationToken>,
+    streamed_frame: &mut impl Mutex<T = StreamedFrame>,
+  ) {
+    let received = monotonics::now();
+    let time = received.duration_since_epoch().to_micros();
+    for &byte in bytes {
+      let rejected = match self.stream.push(byte, time) {
+        Decoded::Pending => continue,
+        Decoded::Frame(rgb) => {
+          streamed_frame.lock(|frame| frame.set(rgb, received));
+          continue;
+        }
+        Decoded::Rejected(rejected) => rejected,
+      };
+      for &byte in rejected {
+        match self.line.push(byte, echo) {
+          Some(Ok(line)) => shell::execute(line, config, remote_input, show_cancellation_token),
+          Some(Err(e)) => uprintln!("error: {}", e),
+          None => {}
+        }
+      }
+    }
+  }
+}
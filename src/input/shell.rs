@@ -0,0 +1,178 @@
+//! Carries out the commands of the shell, see `arclib::shell`.
+
+use alloc::format;
+use arclib::{
+  calibration::Calibration,
+  control::{Command, KeyMap, ShowSpec, SHOW_NAMES},
+  geometry::Geometry,
+  nl,
+  power::PowerLimit,
+  shell::{Setting, ShellCommand, ShellError, HELP},
+  show::Transition,
+  Fix32,
+};
+use rtic::Mutex;
+
+use crate::{
+  app::input_task, config::Config, driver::ShowCancellationToken, storage::save_settings, uprintln,
+};
+
+use super::{apply_command, remote::RemoteInput};
+
+/// Executes a line and replies to it, errors are replied as `error: <reason>`.
+pub fn execute(
+  line: &str,
+  config: &mut impl Mutex<T = Config>,
+  remote_input: &mut impl Mutex<T = RemoteInput>,
+  show_cancellation_token: &mut impl Mutex<T = ShowCancellationToken>,
+) {
+  let result = match ShellCommand::parse(line) {
+    Some(Ok(command)) => run(command, config, remote_input, show_cancellation_token),
+    Some(Err(e)) => Err(e),
+    None => Ok(()),
+  };
+  if let Err(e) = result {
+    uprintln!("error: {}", e);
+  }
+}
+
+fn run(
+  command: ShellCommand,
+  config: &mut impl Mutex<T = Config>,
+  remote_input: &mut impl Mutex<T = RemoteInput>,
+  show_cancellation_token: &mut impl Mutex<T = ShowCancellationToken>,
+) -> Result<(), ShellError> {
+  let mut apply = |command| {
+    apply_command(command, config, remote_input, show_cancellation_token)
+      .map_err(ShellError::InvalidArgument)
+  };
+  match command {
+    ShellCommand::Help => uprintln!("{}", HELP),
+    ShellCommand::Status => {
+      let (spec, brightness, settings) = config.lock(|config| {
+        (
+          config.show_spec.clone(),
+          config.brightness,
+          [
+            Setting::Calibration,
+            Setting::Power,
+            Setting::Transition,
+            Setting::Geometry,
+            Setting::Keys,
+          ]
+          .map(|setting| SettingValue::read(config, setting)),
+        )
+      });
+      print_show(spec);
+      print_brightness(brightness);
+      for value in settings {
+        value.print();
+      }
+    }
+    ShellCommand::Show(None) => print_show(config.lock(|config| config.show_spec.clone())),
+    ShellCommand::Show(Some(spec)) => {
+      apply(Command::SetShow(spec))?;
+      print_show(config.lock(|config| config.show_spec.clone()));
+    }
+    ShellCommand::Shows => uprintln!("shows: {}", SHOW_NAMES.join(" ")),
+    ShellCommand::Stop => {
+      apply(Command::Stop)?;
+      print_show(config.lock(|config| config.show_spec.clone()));
+    }
+    ShellCommand::Color(color) => {
+      apply(Command::SetShow(ShowSpec::new(&format!(
+        "uniform color=#{:x}",
+        color
+      ))))?;
+      print_show(config.lock(|config| config.show_spec.clone()));
+    }
+    ShellCommand::Brightness(None) => print_brightness(config.lock(|config| config.brightness)),
+    ShellCommand::Brightness(Some(brightness)) => {
+      apply(Command::SetBrightness(brightness))?;
+      print_brightness(config.lock(|config| config.brightness));
+    }
+    ShellCommand::Setting(setting, value) => {
+      config
+        .lock(|config| {
+          if !value.is_empty() {
+            update_setting(config, setting, value)?;
+          }
+          Ok(SettingValue::read(config, setting))
+        })
+        .map_err(ShellError::InvalidArgument)?
+        .print();
+    }
+    ShellCommand::Key(key) => {
+      let action =
+        config
+          .lock(|config| config.keys.action(key))
+          .ok_or(ShellError::InvalidArgument(
+            "the key isn't mapped to an action",
+          ))?;
+      // several keys may arrive at once, before the input task runs
+      input_task::spawn(action)
+        .map_err(|_| ShellError::InvalidArgument("too many keys pending, try again"))?;
+    }
+  }
+  Ok(())
+}
+
+fn update_setting(config: &mut Config, setting: Setting, value: &str) -> Result<(), &'static str> {
+  match setting {
+    Setting::Calibration => config.calibration.update(value)?,
+    Setting::Power => config.power.update(value)?,
+    Setting::Transition => config.transition.update(value)?,
+    Setting::Geometry => {
+      config.geometry.update(value, config.nlights)?;
+      config.geometry_changed = true;
+    }
+    Setting::Keys => config.keys.update(value)?,
+  }
+  if let Setting::Calibration | Setting::Keys = setting {
+    save_settings();
+  }
+  Ok(())
+}
+
+/// A setting copied out of the config, so it is printed without holding the lock
+/// while the UART is slowly written.
+enum SettingValue {
+  Calibration(Calibration),
+  Power(PowerLimit),
+  Transition(Transition),
+  Geometry(Geometry),
+  Keys(KeyMap),
+}
+
+impl SettingValue {
+  fn read(config: &Config, setting: Setting) -> Self {
+    match setting {
+      Setting::Calibration => Self::Calibration(config.calibration),
+      Setting::Power => Self::Power(config.power),
+      Setting::Transition => Self::Transition(config.transition),
+      Setting::Geometry => Self::Geometry(config.geometry.clone()),
+      Setting::Keys => Self::Keys(config.keys.clone()),
+    }
+  }
+
+  fn print(&self) {
+    match self {
+      Self::Calibration(calibration) => uprintln!("calibration: {}", calibration),
+      Self::Power(power) => uprintln!("power: {}", power),
+      Self::Transition(transition) => uprintln!("transition: {}", transition),
+      Self::Geometry(geometry) => uprintln!("geometry: {}", geometry),
+      Self::Keys(keys) => uprintln!("keys: {}", keys),
+    }
+  }
+}
+
+fn print_show(spec: Option<ShowSpec>) {
+  match spec {
+    Some(spec) => uprintln!("show: {}", spec),
+    None => uprintln!("show: none"),
+  }
+}
+
+fn print_brightness(brightness: Fix32) {
+  uprintln!("brightness: {}%", (brightness * nl!(100)).round());
+}
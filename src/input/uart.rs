@@ -1,25 +1,31 @@
 use crate::{
-  app::{
-    input_task,
-    uart_task::{self, SharedResources},
-  },
-  util::uart::UART_PERIPHERAL,
+  app::uart_task::{self, SharedResources},
+  util::uart::{UartWriter, UART_PERIPHERAL},
 };
-use rtic::Mutex;
 
-pub struct UartTask {}
+use super::serial::SerialInput;
+
+pub struct UartTask {
+  input: SerialInput,
+}
 impl UartTask {
-  pub fn init() -> Self {
-    Self {}
+  pub fn init(nlights: usize) -> Self {
+    Self {
+      input: SerialInput::new(nlights),
+    }
   }
 }
 
 pub fn uart_task(ctx: uart_task::Context) {
-  let UartTask {} = ctx.local.uart_task;
-  let SharedResources { mut remote_input } = ctx.shared;
+  let UartTask { input } = ctx.local.uart_task;
+  let SharedResources {
+    mut remote_input,
+    mut config,
+    mut show_cancellation_token,
+    mut streamed_frame,
+  } = ctx.shared;
 
   let mut data = [0u8; 1024];
-  let mut string: &str = "";
   let mut nbytes = 0;
 
   cortex_m::interrupt::free(|cs| {
@@ -34,42 +40,14 @@ pub fn uart_task(ctx: uart_task::Context) {
         let _ = writeln!(uart, "uart read error");
       }
     }
-    match core::str::from_utf8(&data[0..nbytes]) {
-      Ok(s) => string = s,
-      Err(e) => {
-        let _ = writeln!(uart, "uart utf8 error: {}", e);
-      }
-    }
-    let _ = writeln!(uart, "UART echo: {}", string);
   });
 
-  use infrared::remotecontrol as irrc;
-  let action = match string {
-    "1" => irrc::Action::One,
-    "2" => irrc::Action::Two,
-    "3" => irrc::Action::Three,
-    "4" => irrc::Action::Four,
-    "5" => irrc::Action::Five,
-    "6" => irrc::Action::Six,
-    "7" => irrc::Action::Seven,
-    "8" => irrc::Action::Eight,
-    "9" => irrc::Action::Nine,
-    "0" => irrc::Action::Zero,
-    "s" => irrc::Action::Stop,
-    "p" => irrc::Action::Play_Pause,
-    "t" => irrc::Action::Time,
-    "x" => irrc::Action::Teletext,
-    "r" => irrc::Action::Repeat,
-    "?" => irrc::Action::Random,
-    "g" => irrc::Action::Prog,
-    "<" => irrc::Action::Prev,
-    ">" => irrc::Action::Next,
-    "[" => irrc::Action::Rewind,
-    "]" => irrc::Action::Forward,
-    _ => return,
-  };
-  remote_input.lock(|input| {
-    input.0 = Some(action);
-  });
-  input_task::spawn().unwrap();
+  input.handle(
+    &data[..nbytes],
+    &mut UartWriter,
+    &mut config,
+    &mut remote_input,
+    &mut show_cancellation_token,
+    &mut streamed_frame,
+  );
 }
@@ -0,0 +1,47 @@
+use crate::{
+  app::usb_task::{self, SharedResources},
+  util::usb::{UsbWriter, USB},
+};
+
+use super::serial::SerialInput;
+
+pub struct UsbTask {
+  input: SerialInput,
+}
+impl UsbTask {
+  pub fn init(nlights: usize) -> Self {
+    Self {
+      input: SerialInput::new(nlights),
+    }
+  }
+}
+
+/// Keeps the USB device running and handles what's received over its serial port.
+pub fn usb_task(ctx: usb_task::Context) {
+  let UsbTask { input } = ctx.local.usb_task;
+  let SharedResources {
+    mut remote_input,
+    mut config,
+    mut show_cancellation_token,
+    mut streamed_frame,
+  } = ctx.shared;
+
+  let mut data = [0u8; 64];
+  let nbytes = cortex_m::interrupt::free(|cs| {
+    let mut usb = USB.borrow(cs).borrow_mut();
+    let usb = usb.as_mut().expect("usb not initialized");
+    if !usb.device.poll(&mut [&mut usb.serial]) {
+      return 0;
+    }
+    usb.serial.read(&mut data).unwrap_or(0)
+  });
+
+  input.handle(
+    &data[..nbytes],
+    &mut UsbWriter,
+    &mut config,
+    &mut remote_input,
+    &mut show_cancellation_token,
+    &mut streamed_frame,
+  );
+}
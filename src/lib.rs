@@ -1,4 +1,23 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+// allows `nl!` to be used inside of this crate
+extern crate self as arclib;
+
+pub mod calibration;
+pub mod color;
+pub mod control;
+pub mod geometry;
+pub mod noise;
+pub mod output;
+pub mod particle;
+pub mod power;
+pub mod segment;
+pub mod settings;
+pub mod shell;
+pub mod show;
+pub mod stream;
+pub mod strip;
 
 pub type Fix32 = fixed::FixedI32<fixed::types::extra::U16>;
 pub const ZERO: Fix32 = Fix32::ZERO;
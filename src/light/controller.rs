@@ -1,26 +1,27 @@
+use alloc::{vec, vec::Vec};
+use arclib::output::Output;
 use rtic::Mutex;
 
-use crate::{app::shared_resources::config_lock, util::AsmDelay};
+use crate::{app::shared_resources::config_lock, uprintln};
 
-use super::{Lights, NormRgbw};
+use super::{color::RawChannel, Lights, NormRgbw};
 
 /// Raw Controller.
 /// Doesn't have a memory associated.
 pub struct RawController<'a> {
   lights: &'a mut Lights,
-  asm_delay: AsmDelay,
 }
 impl<'a> RawController<'a> {
-  pub fn set_display(&mut self, colors: [u32; Lights::N]) {
-    self.lights.write_iter(colors.into_iter(), self.asm_delay);
+  /// Bypasses brightness, calibration and power limiting.
+  pub fn set_display(&mut self, colors: &[[RawChannel; 4]]) {
+    self.lights.write_iter(colors.iter().copied());
   }
 }
 
 /// Memory Controller trait.
 /// Controllers with associated memory.
-pub trait MemoryController<'a> {
-  const N: usize = Lights::N;
-
+pub trait MemoryController {
+  fn nlights(&self) -> usize;
   fn set(&mut self, i: usize, color: NormRgbw);
   fn get(&self, i: usize) -> NormRgbw;
   /// Doesn't respect brightness
@@ -34,22 +35,46 @@ pub trait MemoryControllerExt {
 /// A memory controller which stores the color type.
 /// Every display needs to convert, but get/set doesn't.
 /// Good if a lot of colors change between displays.
-pub struct ColorMemoryController<'a> {
-  lights: &'a mut Lights,
-  memory: [NormRgbw; Lights::N],
-  asm_delay: AsmDelay,
+pub struct ColorMemoryController {
+  lights: Lights,
+  memory: Vec<NormRgbw>,
+  output: Output,
+  /// Whether the previous frame exceeded the power budget.
+  limited: bool,
 }
-impl<'a> ColorMemoryController<'a> {
-  pub fn new(lights: &'a mut Lights, asm_delay: AsmDelay) -> Self {
-    let memory = [NormRgbw::NONE; Lights::N];
+impl ColorMemoryController {
+  pub fn new(lights: Lights) -> Self {
+    let memory = vec![NormRgbw::NONE; lights.nlights()];
+    let has_white = (0..lights.noutputs())
+      .flat_map(|i| {
+        let white = lights.output_strip(i).order.has_white();
+        lights.output_range(i).map(move |_| white)
+      })
+      .collect();
     Self {
       lights,
       memory,
-      asm_delay,
+      output: Output::new(has_white),
+      limited: false,
+    }
+  }
+
+  /// The whole memory, e.g. for rendering a frame into it.
+  pub fn memory_mut(&mut self) -> &mut [NormRgbw] {
+    &mut self.memory
+  }
+
+  pub fn raw(&mut self) -> RawController {
+    RawController {
+      lights: &mut self.lights,
     }
   }
 }
-impl<'a> MemoryController<'a> for ColorMemoryController<'a> {
+impl MemoryController for ColorMemoryController {
+  fn nlights(&self) -> usize {
+    self.memory.len()
+  }
+
   fn set(&mut self, i: usize, color: NormRgbw) {
     self.memory[i] = color;
   }
@@ -59,21 +84,26 @@ impl<'a> MemoryController<'a> for ColorMemoryController<'a> {
   }
 
   fn display(&mut self, config: &mut config_lock) {
-    let brightness = config.lock(|config| config.brightness);
-    self.lights.write_iter(
-      self
-        .memory
-        .into_iter()
-        .map(|c| c.brightness(brightness))
-        .map(|c| c.into_u32()),
-      self.asm_delay,
-    );
+    let settings = config.lock(|config| config.output());
+    let estimate = self.output.convert(&self.memory, &settings);
+    match (estimate, self.limited) {
+      (Some(estimate), false) => uprintln!(
+        "power limited: {}mA estimated, {}mA budget",
+        estimate,
+        settings.power.budget_ma.unwrap_or_default()
+      ),
+      (None, true) => uprintln!("power no longer limited"),
+      _ => {}
+    }
+    self.limited = estimate.is_some();
+
+    self.lights.write_iter(self.output.raw().iter().copied());
   }
 }
 
-impl<'a, M> MemoryControllerExt for M
+impl<M> MemoryControllerExt for M
 where
-  M: MemoryController<'a>,
+  M: MemoryController,
 {
   fn set_range(&mut self, range: core::ops::Range<usize>, color: NormRgbw) {
     for i in range {
@@ -82,7 +112,7 @@ where
   }
 
   fn set_all(&mut self, color: NormRgbw) {
-    for i in 0..Self::N {
+    for i in 0..self.nlights() {
       self.set(i, color);
     }
   }
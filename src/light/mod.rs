@@ -1,88 +1,284 @@
-pub mod color;
 pub mod controller;
 
-use cortex_m::prelude::_embedded_hal_blocking_delay_DelayUs;
+pub use arclib::color;
+
+use alloc::{vec, vec::Vec};
+use arclib::strip::StripConfig;
+use core::{
+  ops::Range,
+  sync::atomic::{compiler_fence, AtomicBool, AtomicU32, Ordering},
+};
+
 use rp_pico::{
   hal::{
-    self, gpio,
-    pac::PIO0,
-    pio::{PIOExt, Tx, SM0},
+    self,
+    pio::{PIOExt, StateMachineIndex, UninitStateMachine, PIO},
   },
   pac,
 };
 
-use self::color::NormRgbw;
-use crate::util::AsmDelay;
+use self::color::{pack, NormRgbw, RawChannel};
+use crate::app::{self, lights_dma_task, lights_latch_task};
+
+type Duration = <app::Monotonic as rtic::Monotonic>::Duration;
+
+/// One state machine per output, on both PIO blocks.
+pub const MAX_OUTPUTS: usize = 8;
+
+/// A strip connected to a GPIO pin.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputConfig {
+  pub pin: u8,
+  pub strip: StripConfig,
+}
 
+struct Output {
+  strip: StripConfig,
+  /// Position of the first light in the logical strip.
+  offset: usize,
+  /// Double buffer, one is sent while the other one is written.
+  buffers: [Vec<u32>; 2],
+}
+
+/// All outputs, concatenated into one logical strip.
+/// Output `i` runs on state machine `i % 4` of PIO block `i / 4` and uses DMA channel `i`.
 pub struct Lights {
-  tx: Tx<(PIO0, SM0)>,
+  dma: pac::DMA,
+  outputs: Vec<Output>,
+  /// Index of the buffers which are sent.
+  front: usize,
 }
 
-pub type LightsPin = gpio::Pin<gpio::bank0::Gpio2, gpio::FunctionPio0>;
-const LIGHTS_PIN_IDX: u8 = 2;
+/// Joined TX FIFO plus output shift register.
+const QUEUED_WORDS: u32 = 8 + 1;
+const FUNCSEL_PIO0: u8 = 6;
+const FUNCSEL_PIO1: u8 = 7;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const READY_INIT: AtomicBool = AtomicBool::new(true);
+#[allow(clippy::declare_interior_mutable_const)]
+const LATCH_TIME_INIT: AtomicU32 = AtomicU32::new(0);
+/// Set when the previous frame of the output has been latched and a new one can be sent.
+static READY: [AtomicBool; MAX_OUTPUTS] = [READY_INIT; MAX_OUTPUTS];
+/// After the DMA transfer is done, the queued words still need to be shifted out
+/// and then the line must be held low, for the strip to latch the frame.
+/// In microseconds, depends on the strip.
+static LATCH_TIME: [AtomicU32; MAX_OUTPUTS] = [LATCH_TIME_INIT; MAX_OUTPUTS];
 
 impl Lights {
-  pub const N: usize = 4 * 60;
   pub fn init(
-    pio_instance: pac::PIO0,
+    configs: &[OutputConfig],
+    pio0: pac::PIO0,
+    pio1: pac::PIO1,
+    dma: pac::DMA,
     resets: &mut pac::RESETS,
     sysclock_freq: f32,
-    _lights_pin: LightsPin,
   ) -> Self {
-    let side_set = pio::SideSet::new(false, 1, false);
-
-    let mut assembler = pio::Assembler::new_with_side_set(side_set);
-
-    const T1: u8 = 2;
-    const T2: u8 = 5;
-    const T3: u8 = 3;
-    const CYCLES_PER_BIT: u32 = (T1 + T2 + T3) as u32;
-    const FREQ: u32 = 800_000;
-
-    let mut wrap_target = assembler.label();
-    let mut do_one = assembler.label();
-    let mut do_zero = assembler.label();
-    let mut wrap_source = assembler.label();
-
-    assembler.bind(&mut wrap_target);
-    assembler.out_with_delay_and_side_set(pio::OutDestination::X, 1, T3 - 1, 0);
-    assembler.jmp_with_delay_and_side_set(pio::JmpCondition::XIsZero, &mut do_zero, T1 - 1, 1);
-    assembler.bind(&mut do_one);
-    assembler.jmp_with_delay_and_side_set(pio::JmpCondition::Always, &mut wrap_target, T2 - 1, 1);
-    assembler.bind(&mut do_zero);
-    assembler.nop_with_delay_and_side_set(T2 - 1, 0);
-    assembler.bind(&mut wrap_source);
-
-    let program = assembler.assemble_with_wrap(wrap_source, wrap_target);
-    let (mut pio, sm, _, _, _) = pio_instance.split(resets);
-    let installed = pio.install(&program).unwrap();
-
-    let div = sysclock_freq / (FREQ as f32 * CYCLES_PER_BIT as f32);
-
-    let (mut sm, _, tx) = hal::pio::PIOBuilder::from_program(installed)
-      .buffers(hal::pio::Buffers::OnlyTx)
-      .out_shift_direction(hal::pio::ShiftDirection::Left)
-      .autopull(true)
-      .pull_threshold(32)
-      .clock_divisor(div)
-      .side_set_pin_base(2)
-      .build(sm);
-
-    sm.set_pindirs([(LIGHTS_PIN_IDX, hal::pio::PinDir::Output)]);
-
-    sm.start();
-
-    Self { tx }
+    assert!(configs.len() <= MAX_OUTPUTS, "too many outputs");
+
+    resets.reset.modify(|_, w| w.dma().clear_bit());
+    while resets.reset_done.read().dma().bit_is_clear() {}
+
+    let (mut pio0, sm00, sm01, sm02, sm03) = pio0.split(resets);
+    let (mut pio1, sm10, sm11, sm12, sm13) = pio1.split(resets);
+
+    macro_rules! start {
+      ($i:expr, $pio:ident, $sm:ident, $funcsel:expr) => {
+        if let Some(config) = configs.get($i) {
+          start_output($i, config, &mut $pio, $sm, $funcsel, &dma, sysclock_freq);
+        }
+      };
+    }
+    start!(0, pio0, sm00, FUNCSEL_PIO0);
+    start!(1, pio0, sm01, FUNCSEL_PIO0);
+    start!(2, pio0, sm02, FUNCSEL_PIO0);
+    start!(3, pio0, sm03, FUNCSEL_PIO0);
+    start!(4, pio1, sm10, FUNCSEL_PIO1);
+    start!(5, pio1, sm11, FUNCSEL_PIO1);
+    start!(6, pio1, sm12, FUNCSEL_PIO1);
+    start!(7, pio1, sm13, FUNCSEL_PIO1);
+
+    let mut offset = 0;
+    let outputs = configs
+      .iter()
+      .map(|config| {
+        let nlights = config.strip.nlights;
+        let output = Output {
+          strip: config.strip,
+          offset,
+          buffers: [vec![0; nlights], vec![0; nlights]],
+        };
+        offset += nlights;
+        output
+      })
+      .collect();
+
+    Self {
+      dma,
+      outputs,
+      front: 0,
+    }
   }
 
-  fn write_iter(&mut self, words: impl Iterator<Item = u32>, mut asm_delay: AsmDelay) {
-    for word in words {
-      // idle write until the fifo isn't full anymore
-      while !self.tx.write(word) {}
+  /// Number of lights of all outputs together.
+  pub fn nlights(&self) -> usize {
+    self
+      .outputs
+      .last()
+      .map_or(0, |o| o.offset + o.strip.nlights)
+  }
+
+  pub fn noutputs(&self) -> usize {
+    self.outputs.len()
+  }
+
+  pub fn output_strip(&self, output: usize) -> StripConfig {
+    self.outputs[output].strip
+  }
+
+  /// Range of the output in the logical strip.
+  pub fn output_range(&self, output: usize) -> Range<usize> {
+    let output = &self.outputs[output];
+    output.offset..(output.offset + output.strip.nlights)
+  }
+
+  /// Packs the colors of the logical strip for the outputs and sends them.
+  /// Only waits if the previous frame is still being sent.
+  fn write_iter(&mut self, mut colors: impl Iterator<Item = [RawChannel; 4]>) {
+    let back = self.front ^ 1;
+    for output in &mut self.outputs {
+      let order = output.strip.order;
+      for word in output.buffers[back].iter_mut() {
+        *word = pack(colors.next().unwrap_or([0; 4]), order);
+      }
     }
-    // TODO: instead of wait, start a countdown and wait on the next iteration
-    // wait until fifo is empty
-    while !self.tx.is_empty() {}
-    asm_delay.delay_us(80);
+
+    for ready in &READY[..self.outputs.len()] {
+      while !ready.load(Ordering::Acquire) {}
+      ready.store(false, Ordering::Relaxed);
+    }
+
+    self.front = back;
+    // the buffers must be written before the DMA starts reading them
+    compiler_fence(Ordering::SeqCst);
+
+    let mut channels = 0;
+    for (i, output) in self.outputs.iter().enumerate() {
+      let buffer = &output.buffers[self.front];
+      let ch = &self.dma.ch[i];
+      ch.ch_read_addr
+        .write(|w| unsafe { w.bits(buffer.as_ptr() as u32) });
+      ch.ch_trans_count
+        .write(|w| unsafe { w.bits(buffer.len() as u32) });
+      channels |= 1 << i;
+    }
+    // start all outputs at once
+    self
+      .dma
+      .multi_chan_trigger
+      .write(|w| unsafe { w.bits(channels) });
   }
 }
+
+/// Sets up the state machine of the output and its DMA channel.
+fn start_output<P: PIOExt, SM: StateMachineIndex>(
+  i: usize,
+  config: &OutputConfig,
+  pio: &mut PIO<P>,
+  sm: UninitStateMachine<(P, SM)>,
+  funcsel: u8,
+  dma: &pac::DMA,
+  sysclock_freq: f32,
+) {
+  let timing = config.strip.timing;
+  let (t1, t2, t3) = (timing.t1, timing.t2, timing.t3);
+  let bits_per_pixel = config.strip.order.bits_per_pixel();
+
+  let side_set = pio::SideSet::new(false, 1, false);
+
+  let mut assembler = pio::Assembler::new_with_side_set(side_set);
+
+  let mut wrap_target = assembler.label();
+  let mut do_one = assembler.label();
+  let mut do_zero = assembler.label();
+  let mut wrap_source = assembler.label();
+
+  assembler.bind(&mut wrap_target);
+  assembler.out_with_delay_and_side_set(pio::OutDestination::X, 1, t3 - 1, 0);
+  assembler.jmp_with_delay_and_side_set(pio::JmpCondition::XIsZero, &mut do_zero, t1 - 1, 1);
+  assembler.bind(&mut do_one);
+  assembler.jmp_with_delay_and_side_set(pio::JmpCondition::Always, &mut wrap_target, t2 - 1, 1);
+  assembler.bind(&mut do_zero);
+  assembler.nop_with_delay_and_side_set(t2 - 1, 0);
+  assembler.bind(&mut wrap_source);
+
+  // every output gets its own copy, so they can have different timings
+  let program = assembler.assemble_with_wrap(wrap_source, wrap_target);
+  let installed = pio.install(&program).unwrap();
+
+  let div = sysclock_freq / (timing.freq as f32 * timing.cycles_per_bit() as f32);
+
+  let (mut sm, _, _) = hal::pio::PIOBuilder::from_program(installed)
+    .buffers(hal::pio::Buffers::OnlyTx)
+    .out_shift_direction(hal::pio::ShiftDirection::Left)
+    .autopull(true)
+    .pull_threshold(bits_per_pixel as u8)
+    .clock_divisor(div)
+    .side_set_pin_base(config.pin)
+    .build(sm);
+
+  // SAFETY: the pin is exclusively used by this output
+  unsafe {
+    (*pac::IO_BANK0::ptr()).gpio[config.pin as usize]
+      .gpio_ctrl
+      .write(|w| w.funcsel().bits(funcsel));
+  }
+  sm.set_pindirs([(config.pin, hal::pio::PinDir::Output)]);
+
+  sm.start();
+
+  let latch_time = QUEUED_WORDS * bits_per_pixel * 1_000_000 / timing.freq + timing.reset_us;
+  LATCH_TIME[i].store(latch_time, Ordering::Relaxed);
+
+  let pio_idx = i / 4;
+  let sm_idx = i % 4;
+  let pio_regs = if pio_idx == 0 {
+    pac::PIO0::ptr()
+  } else {
+    pac::PIO1::ptr()
+  };
+  // SAFETY: the TX FIFO is only written by this channel
+  let txf = unsafe { &(*pio_regs).txf[sm_idx] as *const _ as u32 };
+  let dreq = (8 * pio_idx + sm_idx) as u32;
+
+  let ch = &dma.ch[i];
+  ch.ch_write_addr.write(|w| unsafe { w.bits(txf) });
+  // the alias register doesn't know about the `CTRL` fields
+  let ctrl = 1 << 0 // EN
+    | 2 << 2 // DATA_SIZE: word
+    | 1 << 4 // INCR_READ
+    | (i as u32) << 11 // CHAIN_TO: itself, which disables chaining
+    | dreq << 15; // TREQ_SEL: TX FIFO of the state machine
+  ch.ch_al1_ctrl.write(|w| unsafe { w.bits(ctrl) });
+  dma
+    .inte0
+    .modify(|r, w| unsafe { w.bits(r.bits() | 1 << i) });
+}
+
+/// The DMA transfer of a frame has finished on at least one output.
+pub fn lights_dma_task(_ctx: lights_dma_task::Context) {
+  // SAFETY: only acknowledges the interrupts of our channels
+  let dma = unsafe { &*pac::DMA::ptr() };
+  let finished = dma.ints0.read().bits();
+  dma.ints0.write(|w| unsafe { w.bits(finished) });
+
+  for output in (0..MAX_OUTPUTS).filter(|i| finished & (1 << i) != 0) {
+    let latch_time = LATCH_TIME[output].load(Ordering::Relaxed) as u64;
+    lights_latch_task::spawn_after(Duration::micros(latch_time), output).unwrap();
+  }
+}
+
+/// The strip of the output has latched the frame.
+pub fn lights_latch_task(_ctx: lights_latch_task::Context, output: usize) {
+  READY[output].store(true, Ordering::Release);
+}
@@ -5,11 +5,14 @@
 
 pub mod app;
 pub mod config;
+pub mod driver;
 pub mod input;
 pub mod light;
-pub mod show;
+pub mod storage;
 pub mod util;
 
+pub use arclib::show;
+
 #[allow(unused_imports)]
 #[macro_use]
 extern crate alloc;
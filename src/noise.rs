@@ -0,0 +1,280 @@
+//! Gradient noise in fixed point, random values which change smoothly with the position.
+//!
+//! This is Ken Perlin's improved noise. It's deterministic for a given seed and repeats
+//! every `PERIOD` along every axis, so coordinates can be wrapped to stay in the range of `Fix32`.
+
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+use crate::{nl, Fix32, ONE};
+
+/// The noise repeats after this many units.
+pub const PERIOD: i32 = 256;
+
+/// A noise field, values are roughly between -1 and 1 and zero at integer coordinates.
+#[derive(Clone)]
+pub struct Noise {
+  /// A permutation of `0..PERIOD`, twice so lookups don't have to wrap.
+  perm: [u8; 2 * PERIOD as usize],
+}
+
+impl Noise {
+  pub fn new(seed: u64) -> Self {
+    let mut perm = [0; 2 * PERIOD as usize];
+    for (i, p) in perm.iter_mut().enumerate() {
+      *p = i as u8;
+    }
+    let (first, second) = perm.split_at_mut(PERIOD as usize);
+    first.shuffle(&mut SmallRng::seed_from_u64(seed));
+    second.copy_from_slice(first);
+    Self { perm }
+  }
+
+  fn hash(&self, i: usize) -> usize {
+    self.perm[i] as usize
+  }
+
+  pub fn noise1(&self, x: Fix32) -> Fix32 {
+    let (xi, x) = split(x);
+    let a = self.hash(xi);
+    let b = self.hash(xi + 1);
+    let value = lerp(grad1(a, x), grad1(b, x - ONE), fade(x));
+    (value * 2).clamp(-ONE, ONE)
+  }
+
+  pub fn noise2(&self, x: Fix32, y: Fix32) -> Fix32 {
+    let (xi, x) = split(x);
+    let (yi, y) = split(y);
+    let a = self.hash(xi) + yi;
+    let b = self.hash(xi + 1) + yi;
+    let (u, v) = (fade(x), fade(y));
+    let value = lerp(
+      lerp(
+        grad2(self.hash(a), x, y),
+        grad2(self.hash(b), x - ONE, y),
+        u,
+      ),
+      lerp(
+        grad2(self.hash(a + 1), x, y - ONE),
+        grad2(self.hash(b + 1), x - ONE, y - ONE),
+        u,
+      ),
+      v,
+    );
+    // the largest values are around `sqrt(1 / 2)`
+    (value * nl!(1.41)).clamp(-ONE, ONE)
+  }
+
+  pub fn noise3(&self, x: Fix32, y: Fix32, z: Fix32) -> Fix32 {
+    let (xi, x) = split(x);
+    let (yi, y) = split(y);
+    let (zi, z) = split(z);
+    let a = self.hash(xi) + yi;
+    let aa = self.hash(a) + zi;
+    let ab = self.hash(a + 1) + zi;
+    let b = self.hash(xi + 1) + yi;
+    let ba = self.hash(b) + zi;
+    let bb = self.hash(b + 1) + zi;
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+    let (x1, y1, z1) = (x - ONE, y - ONE, z - ONE);
+    let value = lerp(
+      lerp(
+        lerp(
+          grad3(self.hash(aa), x, y, z),
+          grad3(self.hash(ba), x1, y, z),
+          u,
+        ),
+        lerp(
+          grad3(self.hash(ab), x, y1, z),
+          grad3(self.hash(bb), x1, y1, z),
+          u,
+        ),
+        v,
+      ),
+      lerp(
+        lerp(
+          grad3(self.hash(aa + 1), x, y, z1),
+          grad3(self.hash(ba + 1), x1, y, z1),
+          u,
+        ),
+        lerp(
+          grad3(self.hash(ab + 1), x, y1, z1),
+          grad3(self.hash(bb + 1), x1, y1, z1),
+          u,
+        ),
+        v,
+      ),
+      w,
+    );
+    value.clamp(-ONE, ONE)
+  }
+
+  /// Sums `octaves` layers of noise, each with twice the detail and half the amplitude
+  /// of the previous one. Gives more natural looking structures than a single layer.
+  pub fn fractal3(&self, mut x: Fix32, mut y: Fix32, mut z: Fix32, octaves: u32) -> Fix32 {
+    let mut sum = Fix32::ZERO;
+    let mut total = Fix32::ZERO;
+    let mut amplitude = ONE;
+    for _ in 0..octaves {
+      sum += amplitude * self.noise3(x, y, z);
+      total += amplitude;
+      amplitude /= 2;
+      (x, y, z) = (wrap(x * 2), wrap(y * 2), wrap(z * 2));
+    }
+    if total == Fix32::ZERO {
+      total
+    } else {
+      sum / total
+    }
+  }
+}
+
+/// The equivalent coordinate in `[0, PERIOD)`.
+pub fn wrap(x: Fix32) -> Fix32 {
+  Fix32::from_bits(x.to_bits() & ((PERIOD << 16) - 1))
+}
+
+/// Splits a coordinate into its cell, wrapped to the period, and the position within the cell.
+fn split(x: Fix32) -> (usize, Fix32) {
+  let bits = x.to_bits();
+  (
+    ((bits >> 16) & (PERIOD - 1)) as usize,
+    Fix32::from_bits(bits & 0xffff),
+  )
+}
+
+/// `6t^5 - 15t^4 + 10t^3`, so the noise is smooth across cells.
+fn fade(t: Fix32) -> Fix32 {
+  t * t * t * (t * (t * 6 - nl!(15)) + nl!(10))
+}
+
+fn lerp(a: Fix32, b: Fix32, t: Fix32) -> Fix32 {
+  a + t * (b - a)
+}
+
+/// One of 16 gradients between -1 and 1.
+fn grad1(hash: usize, x: Fix32) -> Fix32 {
+  let g = nl!((hash & 7) + 1) / 8;
+  if hash & 8 == 0 {
+    g * x
+  } else {
+    -g * x
+  }
+}
+
+/// One of the 8 directions to the corners and edges of a square.
+fn grad2(hash: usize, x: Fix32, y: Fix32) -> Fix32 {
+  match hash & 7 {
+    0 => x + y,
+    1 => -x + y,
+    2 => x - y,
+    3 => -x - y,
+    4 => x,
+    5 => -x,
+    6 => y,
+    _ => -y,
+  }
+}
+
+/// One of the 12 directions to the edges of a cube, 4 of them twice.
+fn grad3(hash: usize, x: Fix32, y: Fix32, z: Fix32) -> Fix32 {
+  let h = hash & 15;
+  let u = if h < 8 { x } else { y };
+  let v = match h {
+    0..=3 => y,
+    12 | 14 => x,
+    _ => z,
+  };
+  let u = if h & 1 == 0 { u } else { -u };
+  let v = if h & 2 == 0 { v } else { -v };
+  u + v
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  /// Points spread over the whole period, most of them between the lattice points.
+  fn points() -> impl Iterator<Item = (Fix32, Fix32, Fix32)> {
+    (0..2000).map(|i: i32| {
+      let coordinate =
+        |factor: i32| Fix32::from_bits(i.wrapping_mul(factor) & ((PERIOD << 16) - 1));
+      (coordinate(48271), coordinate(69621), coordinate(16807))
+    })
+  }
+
+  fn samples(noise: &Noise) -> Vec<[Fix32; 3]> {
+    points()
+      .map(|(x, y, z)| [noise.noise1(x), noise.noise2(x, y), noise.noise3(x, y, z)])
+      .collect()
+  }
+
+  #[test]
+  fn is_deterministic_per_seed() {
+    assert_eq!(samples(&Noise::new(1)), samples(&Noise::new(1)));
+    assert_ne!(samples(&Noise::new(1)), samples(&Noise::new(2)));
+  }
+
+  #[test]
+  fn is_zero_at_lattice_points() {
+    let noise = Noise::new(3);
+    for i in [0, 1, 17, 255, 256, -1] {
+      let i = nl!(i);
+      assert_eq!(noise.noise1(i), Fix32::ZERO);
+      assert_eq!(noise.noise2(i, nl!(4)), Fix32::ZERO);
+      assert_eq!(noise.noise3(nl!(7), i, nl!(-3)), Fix32::ZERO);
+    }
+  }
+
+  #[test]
+  fn stays_within_range() {
+    let noise = Noise::new(4);
+    for (x, y, z) in points() {
+      for value in [
+        noise.noise1(x),
+        noise.noise2(x, y),
+        noise.noise3(x, y, z),
+        noise.fractal3(x, y, z, 3),
+      ] {
+        assert!(
+          (-ONE..=ONE).contains(&value),
+          "{} at {} {} {}",
+          value,
+          x,
+          y,
+          z
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn is_continuous_across_cells() {
+    let noise = Noise::new(5);
+    let eps = nl!(1) / 1024;
+    for i in 0..PERIOD {
+      let (before, after) = (nl!(i) - eps, nl!(i) + eps);
+      let (y, z) = (nl!(0.3), nl!(0.6));
+      assert!((noise.noise1(before) - noise.noise1(after)).abs() < nl!(0.01));
+      assert!((noise.noise2(before, y) - noise.noise2(after, y)).abs() < nl!(0.01));
+      assert!((noise.noise3(y, before, z) - noise.noise3(y, after, z)).abs() < nl!(0.01));
+    }
+  }
+
+  #[test]
+  fn repeats_after_the_period() {
+    let noise = Noise::new(6);
+    let period = nl!(PERIOD);
+    for (x, y, z) in points().take(200) {
+      assert_eq!(noise.noise1(x), noise.noise1(x + period));
+      assert_eq!(noise.noise2(x, y), noise.noise2(x - period, y));
+      assert_eq!(noise.noise3(x, y, z), noise.noise3(x, y, z + period));
+      assert_eq!(wrap(x + period), x);
+      assert_eq!(wrap(x - period), x);
+    }
+    // the last cell continues with the first one
+    let eps = nl!(1) / 1024;
+    assert!((noise.noise1(period - eps) - noise.noise1(eps)).abs() < nl!(0.01));
+  }
+}
@@ -0,0 +1,122 @@
+//! Conversion of the rendered frames into the channels sent to the strip.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+  calibration::{Calibration, CalibrationTable},
+  color::{denormalize_dithered, NormRgbw, RawChannel},
+  power::PowerLimit,
+  Fix32, ONE, ZERO,
+};
+
+/// Everything applied to the frames on their way to the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputSettings {
+  pub brightness: Fix32,
+  pub calibration: Calibration,
+  pub power: PowerLimit,
+  /// Temporal dithering of the output, for more than 8 bits at low brightness.
+  pub dithering: bool,
+}
+
+impl Default for OutputSettings {
+  fn default() -> Self {
+    Self {
+      brightness: ONE,
+      calibration: Calibration::default(),
+      power: PowerLimit::default(),
+      dithering: true,
+    }
+  }
+}
+
+/// Converts frames for a strip, keeping what carries over from one frame to the next.
+pub struct Output {
+  /// Rebuilt when the calibration changes.
+  calibration: CalibrationTable,
+  /// Whether the light is on a strip with a white LED.
+  has_white: Vec<bool>,
+  raw: Vec<[RawChannel; 4]>,
+  /// Rounding errors of the channels, carried over to the next frame.
+  error: Vec<[Fix32; 4]>,
+}
+
+impl Output {
+  pub fn new(has_white: Vec<bool>) -> Self {
+    let n = has_white.len();
+    Self {
+      calibration: CalibrationTable::new(Calibration::default()),
+      has_white,
+      raw: vec![[0; 4]; n],
+      error: vec![[ZERO; 4]; n],
+    }
+  }
+
+  /// Applies the brightness, the calibration, the dithering and the power limit, in that order.
+  /// Returns the estimated current before limiting if the frame was limited.
+  pub fn convert(&mut self, frame: &[NormRgbw], settings: &OutputSettings) -> Option<u32> {
+    if self.calibration.calibration() != settings.calibration {
+      self.calibration = settings.calibration.table();
+    }
+
+    let lights = frame.iter().zip(&self.has_white).zip(&mut self.error);
+    for (raw, ((color, &has_white), error)) in self.raw.iter_mut().zip(lights) {
+      let color = self
+        .calibration
+        .apply(color.brightness(settings.brightness), has_white);
+      *raw = if settings.dithering {
+        denormalize_dithered(color.into_channel_array(), error)
+      } else {
+        color.into_u8_channel_array()
+      };
+      // not sent, so it doesn't draw any current
+      if !has_white {
+        raw[3] = 0;
+      }
+    }
+
+    settings.power.limit(&mut self.raw)
+  }
+
+  /// The channels of the last converted frame.
+  pub fn raw(&self) -> &[[RawChannel; 4]] {
+    &self.raw
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::nl;
+
+  #[test]
+  fn drops_the_white_of_lights_without_white_led() {
+    let mut output = Output::new(vec![true, false]);
+    output.convert(&[NormRgbw::RGBW; 2], &OutputSettings::default());
+    assert_eq!(output.raw(), [[255; 4], [255, 255, 255, 0]]);
+  }
+
+  #[test]
+  fn dithers_between_the_channel_values() {
+    let mut output = Output::new(vec![true]);
+    let half_step = NormRgbw::new(nl!(0.5) / 255, ZERO, ZERO, ZERO);
+    let reds: Vec<_> = (0..64)
+      .map(|_| {
+        output.convert(&[half_step], &OutputSettings::default());
+        output.raw()[0][0]
+      })
+      .collect();
+    // on every other frame on average
+    assert!(reds.iter().all(|&red| red <= 1));
+    let lit = reds.iter().filter(|&&red| red == 1).count();
+    assert!((30..=34).contains(&lit), "{} of 64", lit);
+  }
+
+  #[test]
+  fn limits_the_power() {
+    let mut output = Output::new(vec![true; 200]);
+    let estimate = output.convert(&[NormRgbw::RGBW; 200], &OutputSettings::default());
+    assert_eq!(estimate, Some(200 * 4 * 12 + 200));
+    assert!(output.raw()[0][0] < 255);
+  }
+}
@@ -0,0 +1,198 @@
+//! Particles moving along the strip, with positions between the lights.
+//!
+//! Positions are in lights from the start of the strip, velocities in lights per second
+//! and accelerations in lights per second squared.
+
+use alloc::vec::Vec;
+
+use crate::{
+  color::{BlendMode, NormRgbw},
+  nl,
+  show::Micros,
+  Fix32, ONE, ZERO,
+};
+
+/// Longer steps are simulated as this long, so particles don't tunnel through the ends.
+const MAX_STEP: Micros = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Particle {
+  pub pos: Fix32,
+  pub vel: Fix32,
+  /// Negative values pull towards the start of the strip.
+  pub gravity: Fix32,
+  /// Share of the velocity lost per second.
+  pub damping: Fix32,
+  /// Seconds since the particle was spawned.
+  pub age: Fix32,
+  /// Seconds until the particle has faded out, `None` for particles which live forever.
+  pub lifetime: Option<Fix32>,
+  pub color: NormRgbw,
+}
+
+impl Particle {
+  /// A particle which stays at `pos` forever.
+  pub fn new(pos: Fix32, color: NormRgbw) -> Self {
+    Self {
+      pos,
+      vel: ZERO,
+      gravity: ZERO,
+      damping: ZERO,
+      age: ZERO,
+      lifetime: None,
+      color,
+    }
+  }
+
+  #[must_use]
+  pub fn with_velocity(self, vel: Fix32) -> Self {
+    Self { vel, ..self }
+  }
+
+  #[must_use]
+  pub fn with_gravity(self, gravity: Fix32) -> Self {
+    Self { gravity, ..self }
+  }
+
+  #[must_use]
+  pub fn with_damping(self, damping: Fix32) -> Self {
+    Self { damping, ..self }
+  }
+
+  #[must_use]
+  pub fn with_lifetime(self, lifetime: Fix32) -> Self {
+    Self {
+      lifetime: Some(lifetime),
+      ..self
+    }
+  }
+
+  /// Fades out linearly over the lifetime.
+  pub fn brightness(&self) -> Fix32 {
+    match self.lifetime {
+      Some(lifetime) if lifetime > ZERO => (ONE - self.age / lifetime).clamp(ZERO, ONE),
+      Some(_) => ZERO,
+      None => ONE,
+    }
+  }
+
+  pub fn is_alive(&self) -> bool {
+    match self.lifetime {
+      Some(lifetime) => self.age < lifetime,
+      None => true,
+    }
+  }
+
+  fn step(&mut self, dt: Fix32) {
+    self.vel += self.gravity * dt;
+    self.vel -= self.vel * (self.damping * dt).min(ONE);
+    self.pos += self.vel * dt;
+    self.age += dt;
+  }
+}
+
+/// What happens to particles reaching an end of the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edges {
+  /// They bounce back, keeping this share of their velocity.
+  Bounce(Fix32),
+  /// They continue at the other end.
+  Wrap,
+  /// They are removed once they left the strip.
+  Vanish,
+}
+
+/// All the particles of a show.
+pub struct Particles {
+  particles: Vec<Particle>,
+  pub edges: Edges,
+}
+
+impl Particles {
+  pub fn new(edges: Edges) -> Self {
+    Self {
+      particles: Vec::new(),
+      edges,
+    }
+  }
+
+  pub fn spawn(&mut self, particle: Particle) {
+    self.particles.push(particle);
+  }
+
+  pub fn len(&self) -> usize {
+    self.particles.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.particles.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.particles.clear();
+  }
+
+  /// Keeps only the particles for which `keep` returns true.
+  pub fn retain(&mut self, keep: impl FnMut(&Particle) -> bool) {
+    self.particles.retain(keep);
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+    self.particles.iter()
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Particle> {
+    self.particles.iter_mut()
+  }
+
+  /// Moves the particles by `delta` on a strip of `len` lights
+  /// and removes the ones which faded out or vanished.
+  pub fn step(&mut self, delta: Micros, len: usize) {
+    let dt = Fix32::from_bits((delta.min(MAX_STEP) * (1 << 16) / 1_000_000) as i32);
+    let end = nl!(len.max(1) - 1);
+    let edges = self.edges;
+    self.particles.retain_mut(|p| {
+      p.step(dt);
+      match edges {
+        Edges::Bounce(restitution) => {
+          if p.pos < ZERO {
+            p.pos = -p.pos;
+            p.vel = -p.vel * restitution;
+          } else if p.pos > end {
+            p.pos = (2 * end - p.pos).max(ZERO);
+            p.vel = -p.vel * restitution;
+          }
+        }
+        Edges::Wrap => p.pos = p.pos.rem_euclid(end + ONE),
+        Edges::Vanish => {
+          if p.pos < -ONE || p.pos > end + ONE {
+            return false;
+          }
+        }
+      }
+      p.is_alive()
+    });
+  }
+
+  /// Adds the particles to `frame`.
+  /// Every particle is split between the two lights it's between, so it moves smoothly.
+  pub fn render(&self, frame: &mut [NormRgbw]) {
+    let n = frame.len();
+    if n == 0 {
+      return;
+    }
+    for p in &self.particles {
+      let color = p.color.scale(p.brightness());
+      let i = p.pos.floor().to_num::<i32>();
+      let frac = p.pos.frac();
+      for (i, weight) in [(i, ONE - frac), (i + 1, frac)] {
+        let i = match self.edges {
+          Edges::Wrap => i.rem_euclid(n as i32) as usize,
+          _ if i < 0 || i >= n as i32 => continue,
+          _ => i as usize,
+        };
+        frame[i] = frame[i].blend(color.scale(weight), BlendMode::Add, ONE);
+      }
+    }
+  }
+}
@@ -0,0 +1,179 @@
+//! Estimation and limiting of the current drawn by the strip.
+
+use core::fmt;
+
+use crate::color::RawChannel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerLimit {
+  /// Current of the channels `[r, g, b, w]` of a light at full duty, in mA.
+  pub channel_ma: [u32; 4],
+  /// Current of a light which is off, in mA.
+  pub idle_ma: u32,
+  /// Maximum current of all lights together, in mA.
+  pub budget_ma: Option<u32>,
+}
+
+impl Default for PowerLimit {
+  /// SK6812 lights on a 5V 4A supply.
+  fn default() -> Self {
+    Self {
+      channel_ma: [12; 4],
+      idle_ma: 1,
+      budget_ma: Some(4000),
+    }
+  }
+}
+
+impl PowerLimit {
+  /// Estimated current of the frame in mA, saturating at `u32::MAX`.
+  pub fn estimate(&self, frame: &[[RawChannel; 4]]) -> u32 {
+    let (active, idle) = self.estimate_parts(frame);
+    u32::try_from(active + idle).unwrap_or(u32::MAX)
+  }
+
+  /// Current of the lit channels and of all lights when they are off, in mA.
+  /// Computed in `u64`, as any mA value is accepted and strips can be long.
+  fn estimate_parts(&self, frame: &[[RawChannel; 4]]) -> (u64, u64) {
+    let mut duty = [0u64; 4];
+    for color in frame {
+      for (d, &c) in duty.iter_mut().zip(color) {
+        *d += c as u64;
+      }
+    }
+    let active = duty
+      .iter()
+      .zip(self.channel_ma)
+      .map(|(d, ma)| d * ma as u64 / 255)
+      .sum();
+    (active, self.idle_ma as u64 * frame.len() as u64)
+  }
+
+  /// Scales the frame down if its estimated current exceeds the budget.
+  /// Returns the estimate before scaling if the frame was limited.
+  pub fn limit(&self, frame: &mut [[RawChannel; 4]]) -> Option<u32> {
+    let budget = self.budget_ma? as u64;
+    let (active, idle) = self.estimate_parts(frame);
+    if active + idle <= budget {
+      return None;
+    }
+    if active == 0 {
+      // every light is off, there is nothing to scale down
+      return None;
+    }
+    // only the active part can be scaled
+    let available = budget.saturating_sub(idle);
+    for color in frame {
+      for c in color {
+        *c = (*c as u64 * available / active) as RawChannel;
+      }
+    }
+    Some(u32::try_from(active + idle).unwrap_or(u32::MAX))
+  }
+
+  /// Changes a single setting, given as `budget <mA|off>`, `idle <mA>` or `<r|g|b|w> <mA>`.
+  pub fn update(&mut self, setting: &str) -> Result<(), &'static str> {
+    let mut words = setting.split_whitespace();
+    let (name, value) = match (words.next(), words.next(), words.next()) {
+      (Some(name), Some(value), None) => (name, value),
+      _ => return Err("expected `<setting> <mA>`"),
+    };
+    if (name, value) == ("budget", "off") {
+      self.budget_ma = None;
+      return Ok(());
+    }
+    let value: u32 = value.parse().map_err(|_| "invalid number")?;
+    let channel = match name {
+      "budget" => {
+        self.budget_ma = Some(value);
+        return Ok(());
+      }
+      "idle" => {
+        self.idle_ma = value;
+        return Ok(());
+      }
+      "r" => 0,
+      "g" => 1,
+      "b" => 2,
+      "w" => 3,
+      _ => return Err("unknown setting, expected budget, idle, r, g, b or w"),
+    };
+    self.channel_ma[channel] = value;
+    Ok(())
+  }
+}
+
+impl fmt::Display for PowerLimit {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let [r, g, b, w] = self.channel_ma;
+    write!(
+      f,
+      "r {} g {} b {} w {} idle {} budget ",
+      r, g, b, w, self.idle_ma
+    )?;
+    match self.budget_ma {
+      Some(budget) => write!(f, "{}", budget),
+      None => write!(f, "off"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec;
+
+  use super::*;
+
+  #[test]
+  fn estimates_the_current() {
+    let power = PowerLimit::default();
+    assert_eq!(power.estimate(&[[0; 4]; 10]), 10);
+    assert_eq!(power.estimate(&[[255, 0, 0, 255], [0; 4]]), 2 * 12 + 2);
+  }
+
+  #[test]
+  fn limits_frames_over_budget() {
+    let power = PowerLimit::default();
+    let mut frame = vec![[255; 4]; 100];
+    assert_eq!(power.limit(&mut frame), Some(100 * 4 * 12 + 100));
+    assert!(power.estimate(&frame) <= 4000);
+    // the colors keep their ratio
+    assert!(frame.iter().all(|&c| c == frame[0] && c[0] < 255));
+  }
+
+  #[test]
+  fn keeps_frames_within_budget() {
+    let mut power = PowerLimit::default();
+    let mut frame = vec![[255, 128, 0, 0]; 10];
+    assert_eq!(power.limit(&mut frame), None);
+    assert_eq!(frame, vec![[255, 128, 0, 0]; 10]);
+
+    power.update("budget off").unwrap();
+    let mut frame = vec![[255; 4]; 1000];
+    assert_eq!(power.limit(&mut frame), None);
+
+    // the budget doesn't even cover the lights which are off
+    power.update("budget 50").unwrap();
+    assert_eq!(power.limit(&mut frame[..100]), Some(100 * 4 * 12 + 100));
+    assert_eq!(frame[0], [0; 4]);
+    // and all of them are off
+    assert_eq!(power.limit(&mut frame[..100]), None);
+  }
+
+  #[test]
+  fn saturates_large_currents() {
+    let mut power = PowerLimit::default();
+    power.update("r 4000000000").unwrap();
+    power.update("idle 4000000000").unwrap();
+    let mut frame = vec![[255, 0, 0, 0]; 1000];
+    assert_eq!(power.estimate(&frame), u32::MAX);
+    assert_eq!(power.limit(&mut frame), Some(u32::MAX));
+    assert_eq!(frame[0], [0; 4]);
+
+    power.update("idle 0").unwrap();
+    power.update("budget 4000000000").unwrap();
+    let mut frame = vec![[255, 0, 0, 0]; 2];
+    assert_eq!(power.limit(&mut frame), Some(u32::MAX));
+    assert_eq!(frame[0], [127, 0, 0, 0]);
+  }
+}
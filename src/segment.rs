@@ -0,0 +1,84 @@
+//! Named parts of the logical strip.
+
+use core::ops::Range;
+
+use crate::color::NormRgbw;
+
+/// Lights `start..start + len` of the strip.
+/// Reversed segments are addressed from their end, e.g. for strips mounted the other way around.
+/// Lights outside of the segment or the frame are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+  pub name: &'static str,
+  pub start: usize,
+  pub len: usize,
+  pub reverse: bool,
+}
+
+impl Segment {
+  pub const fn new(name: &'static str, start: usize, len: usize) -> Self {
+    Self {
+      name,
+      start,
+      len,
+      reverse: false,
+    }
+  }
+
+  #[must_use]
+  pub const fn reversed(self) -> Self {
+    Self {
+      reverse: !self.reverse,
+      ..self
+    }
+  }
+
+  /// The segment directly after this one.
+  pub const fn next(&self, name: &'static str, len: usize) -> Self {
+    Self::new(name, self.start + self.len, len)
+  }
+
+  /// Lights of the strip covered by the segment.
+  pub const fn range(&self) -> Range<usize> {
+    self.start..(self.start + self.len)
+  }
+
+  /// Index in the strip of the `i`th light of the segment, `None` if it's beyond the segment.
+  pub fn index(&self, i: usize) -> Option<usize> {
+    if i >= self.len {
+      None
+    } else if self.reverse {
+      Some(self.start + self.len - 1 - i)
+    } else {
+      Some(self.start + i)
+    }
+  }
+
+  pub fn get(&self, frame: &[NormRgbw], i: usize) -> Option<NormRgbw> {
+    frame.get(self.index(i)?).copied()
+  }
+
+  pub fn set(&self, frame: &mut [NormRgbw], i: usize, color: NormRgbw) {
+    if let Some(led) = self.index(i).and_then(|i| frame.get_mut(i)) {
+      *led = color;
+    }
+  }
+
+  /// Sets the lights `range` of the segment.
+  pub fn fill_range(&self, frame: &mut [NormRgbw], range: Range<usize>, color: NormRgbw) {
+    for i in range {
+      self.set(frame, i, color);
+    }
+  }
+
+  pub fn fill(&self, frame: &mut [NormRgbw], color: NormRgbw) {
+    self.fill_range(frame, 0..self.len, color);
+  }
+
+  /// Copies `colors`, given in the order of the segment, into the strip.
+  pub fn write(&self, frame: &mut [NormRgbw], colors: &[NormRgbw]) {
+    for (i, &color) in colors.iter().enumerate().take(self.len) {
+      self.set(frame, i, color);
+    }
+  }
+}
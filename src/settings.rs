@@ -0,0 +1,423 @@
+//! Settings which survive a restart, stored as a journal of records in flash.
+//!
+//! Every save writes a record to the next page of the storage region and the newest
+//! valid record wins. A sector is only erased right before the journal wraps around
+//! into it, so the sectors wear evenly and an interrupted save keeps the previous record.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+  calibration::Calibration,
+  color::{NormRgbw, WhiteExtraction},
+  control::{KeyMap, ShowSpec, ACTIONS},
+  show::Memory,
+  Fix32, ONE,
+};
+
+/// Smallest unit which can be programmed, every record takes one page.
+pub const PAGE_SIZE: usize = 256;
+/// Smallest unit which can be erased.
+pub const SECTOR_SIZE: usize = 4096;
+
+const MAGIC: [u8; 4] = *b"ARCS";
+/// Increased whenever the layout of the payload changes, records of other versions are ignored.
+/// Fields appended to the end are optional instead, so existing records stay valid.
+pub const VERSION: u16 = 1;
+/// Magic, version, payload length and sequence number.
+const HEADER_SIZE: usize = 12;
+const CHECKSUM_SIZE: usize = 4;
+/// Longer show specs aren't stored.
+const MAX_SHOW_LEN: usize = 64;
+/// Further key mappings aren't stored.
+const MAX_KEYS: usize = 40;
+/// Values of the show memory with longer keys aren't stored.
+const MAX_MEMORY_KEY_LEN: usize = 8;
+/// Further values of the show memory aren't stored.
+const MAX_MEMORY_VALUES: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+  pub brightness: Fix32,
+  /// The last show which was selected.
+  pub show: Option<ShowSpec>,
+  pub calibration: Calibration,
+  pub keys: KeyMap,
+  /// Values the shows keep, like high scores.
+  pub memory: Memory,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      brightness: ONE,
+      show: None,
+      calibration: Calibration::default(),
+      keys: KeyMap::default(),
+      memory: Memory::default(),
+    }
+  }
+}
+
+impl Settings {
+  /// Encodes the settings into a record, `sequence` orders the records of the journal.
+  pub fn encode(&self, sequence: u32) -> [u8; PAGE_SIZE] {
+    let mut page = [0xff; PAGE_SIZE];
+    let mut payload = Writer::new(&mut page[HEADER_SIZE..PAGE_SIZE - CHECKSUM_SIZE]);
+
+    payload.fix32(self.brightness);
+    payload.fix32(self.calibration.gamma);
+    for &scale in &self.calibration.scale {
+      payload.fix32(scale);
+    }
+    match self.calibration.white {
+      WhiteExtraction::Off => payload.u8(0),
+      WhiteExtraction::On(led) => {
+        payload.u8(1);
+        for channel in led.into_channel_array() {
+          payload.fix32(channel);
+        }
+      }
+    }
+    match &self.show {
+      Some(show) if show.as_str().len() <= MAX_SHOW_LEN => {
+        payload.u8(show.as_str().len() as u8);
+        payload.bytes(show.as_str().as_bytes());
+      }
+      _ => payload.u8(0),
+    }
+    let keys: Vec<_> = self
+      .keys
+      .keys()
+      .iter()
+      .filter_map(|&(key, action)| {
+        let index = ACTIONS.iter().position(|(_, a)| *a == action)?;
+        key.is_ascii().then_some([key as u8, index as u8])
+      })
+      .take(MAX_KEYS)
+      .collect();
+    payload.u8(keys.len() as u8);
+    for key in keys {
+      payload.bytes(&key);
+    }
+    let memory: Vec<_> = self
+      .memory
+      .values()
+      .into_iter()
+      .filter(|(key, _)| key.len() <= MAX_MEMORY_KEY_LEN)
+      .take(MAX_MEMORY_VALUES)
+      .collect();
+    payload.u8(memory.len() as u8);
+    for (key, value) in memory {
+      payload.u8(key.len() as u8);
+      payload.bytes(key.as_bytes());
+      payload.bytes(&value.to_le_bytes());
+    }
+
+    let len = payload.pos;
+    page[0..4].copy_from_slice(&MAGIC);
+    page[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    page[6..8].copy_from_slice(&(len as u16).to_le_bytes());
+    page[8..12].copy_from_slice(&sequence.to_le_bytes());
+    let end = HEADER_SIZE + len;
+    let checksum = crc32(&page[..end]);
+    page[end..end + CHECKSUM_SIZE].copy_from_slice(&checksum.to_le_bytes());
+    page
+  }
+
+  /// Decodes a record into its sequence number and the settings.
+  /// `None` for erased pages, corrupt records and records of other versions.
+  pub fn decode(page: &[u8]) -> Option<(u32, Self)> {
+    let header = page.get(..HEADER_SIZE)?;
+    if header[0..4] != MAGIC || u16::from_le_bytes([header[4], header[5]]) != VERSION {
+      return None;
+    }
+    let len = u16::from_le_bytes([header[6], header[7]]) as usize;
+    let sequence = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    let end = HEADER_SIZE + len;
+    let checksum = page.get(end..end + CHECKSUM_SIZE)?;
+    if crc32(&page[..end]).to_le_bytes() != checksum {
+      return None;
+    }
+
+    let mut payload = Reader::new(&page[HEADER_SIZE..end]);
+    let brightness = payload.fix32()?;
+    let gamma = payload.fix32()?;
+    let mut scale = [ONE; 4];
+    for scale in &mut scale {
+      *scale = payload.fix32()?;
+    }
+    let white = match payload.u8()? {
+      0 => WhiteExtraction::Off,
+      1 => {
+        let mut led = [ONE; 4];
+        for channel in &mut led {
+          *channel = payload.fix32()?;
+        }
+        WhiteExtraction::On(NormRgbw::from_channel_array(led))
+      }
+      _ => return None,
+    };
+    let show = match payload.u8()? as usize {
+      0 => None,
+      len => Some(ShowSpec::new(
+        core::str::from_utf8(payload.bytes(len)?).ok()?,
+      )),
+    };
+    let nkeys = payload.u8()? as usize;
+    let mut keys = Vec::with_capacity(nkeys);
+    for _ in 0..nkeys {
+      let key = payload.bytes(2)?;
+      let (_, action) = ACTIONS.get(key[1] as usize)?;
+      keys.push((key[0] as char, *action));
+    }
+    // appended later, missing in older records
+    let nvalues = payload.u8().unwrap_or(0) as usize;
+    let mut memory = Vec::with_capacity(nvalues);
+    for _ in 0..nvalues {
+      let len = payload.u8()? as usize;
+      let key = String::from(core::str::from_utf8(payload.bytes(len)?).ok()?);
+      let value = payload.bytes(4)?;
+      memory.push((
+        key,
+        u32::from_le_bytes([value[0], value[1], value[2], value[3]]),
+      ));
+    }
+
+    let settings = Settings {
+      brightness,
+      show,
+      calibration: Calibration {
+        gamma,
+        scale,
+        white,
+      },
+      keys: KeyMap::new(keys),
+      memory: memory.into_iter().collect(),
+    };
+    Some((sequence, settings))
+  }
+}
+
+/// Where the next record of the journal goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Journal {
+  pages: usize,
+  next: usize,
+  sequence: u32,
+}
+
+/// A record to be written to the storage region.
+pub struct Record {
+  /// Offset of the page within the region.
+  pub offset: usize,
+  /// Whether the sector starting at `offset` has to be erased first.
+  pub erase: bool,
+  pub page: [u8; PAGE_SIZE],
+}
+
+impl Journal {
+  /// Finds the newest record in `region`, which has to consist of whole sectors.
+  pub fn load(region: &[u8]) -> (Self, Option<Settings>) {
+    let pages = region.len() / PAGE_SIZE;
+    let newest = region
+      .chunks_exact(PAGE_SIZE)
+      .enumerate()
+      .filter_map(|(i, page)| Settings::decode(page).map(|(sequence, s)| (i, sequence, s)))
+      // the sequence numbers of the journal are close together, so they are compared
+      // by their distance, which keeps working once they wrap around
+      .max_by(|(_, a, _), (_, b, _)| (a.wrapping_sub(*b) as i32).cmp(&0));
+    let (next, sequence, settings) = match newest {
+      Some((i, sequence, settings)) => (
+        (i + 1) % pages.max(1),
+        sequence.wrapping_add(1),
+        Some(settings),
+      ),
+      None => (0, 0, None),
+    };
+    let mut journal = Self {
+      pages,
+      next,
+      sequence,
+    };
+    // a page which isn't erased can't be programmed, continue with the next sector instead
+    let blank = match region.chunks_exact(PAGE_SIZE).nth(next) {
+      Some(page) => page.iter().all(|&b| b == 0xff),
+      None => true,
+    };
+    if !blank {
+      let pages_per_sector = SECTOR_SIZE / PAGE_SIZE;
+      journal.next = (next / pages_per_sector + 1) * pages_per_sector % pages.max(1);
+    }
+    (journal, settings)
+  }
+
+  /// Encodes the settings into the next record of the journal.
+  pub fn append(&mut self, settings: &Settings) -> Record {
+    let offset = self.next * PAGE_SIZE;
+    let record = Record {
+      offset,
+      erase: matches!(offset % SECTOR_SIZE, 0),
+      page: settings.encode(self.sequence),
+    };
+    self.next = (self.next + 1) % self.pages.max(1);
+    self.sequence = self.sequence.wrapping_add(1);
+    record
+  }
+}
+
+/// CRC-32 as used by zlib.
+fn crc32(data: &[u8]) -> u32 {
+  let mut crc = !0u32;
+  for &byte in data {
+    crc ^= byte as u32;
+    for _ in 0..8 {
+      let mask = (crc & 1).wrapping_neg();
+      crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+    }
+  }
+  !crc
+}
+
+struct Writer<'a> {
+  buf: &'a mut [u8],
+  pos: usize,
+}
+
+impl<'a> Writer<'a> {
+  fn new(buf: &'a mut [u8]) -> Self {
+    Self { buf, pos: 0 }
+  }
+
+  /// The payload is always small enough for a page, see the `MAX_` constants.
+  fn bytes(&mut self, bytes: &[u8]) {
+    self.buf[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+    self.pos += bytes.len();
+  }
+
+  fn u8(&mut self, value: u8) {
+    self.bytes(&[value]);
+  }
+
+  fn fix32(&mut self, value: Fix32) {
+    self.bytes(&value.to_bits().to_le_bytes());
+  }
+}
+
+struct Reader<'a> {
+  buf: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn new(buf: &'a [u8]) -> Self {
+    Self { buf, pos: 0 }
+  }
+
+  fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+    let bytes = self.buf.get(self.pos..self.pos + len)?;
+    self.pos += len;
+    Some(bytes)
+  }
+
+  fn u8(&mut self) -> Option<u8> {
+    self.bytes(1).map(|b| b[0])
+  }
+
+  fn fix32(&mut self) -> Option<Fix32> {
+    let bytes = self.bytes(4)?;
+    Some(Fix32::from_bits(i32::from_le_bytes([
+      bytes[0], bytes[1], bytes[2], bytes[3],
+    ])))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::{string::ToString, vec};
+
+  use super::*;
+  use crate::{color::NormRgbw, control::ShowSpec, nl};
+
+  fn settings() -> Settings {
+    let memory = Memory::default();
+    memory.set("snake", 42);
+    memory.set("too long a key", 1);
+    Settings {
+      brightness: nl!(0.5),
+      show: Some(ShowSpec::new("twinkle density=0.2 palette=ice")),
+      calibration: Calibration {
+        gamma: nl!(2.2),
+        scale: [ONE, nl!(0.9), nl!(0.8), nl!(0.7)],
+        white: WhiteExtraction::On(NormRgbw::YELLOW),
+      },
+      keys: KeyMap::default(),
+      memory,
+    }
+  }
+
+  #[test]
+  fn decodes_what_was_encoded() {
+    let page = settings().encode(7);
+    let (sequence, decoded) = Settings::decode(&page).unwrap();
+    assert_eq!(sequence, 7);
+    // keys which are too long aren't stored
+    let expected = Settings {
+      memory: [("snake".to_string(), 42)].into_iter().collect(),
+      ..settings()
+    };
+    assert_eq!(decoded, expected);
+  }
+
+  #[test]
+  fn rejects_corrupt_and_erased_pages() {
+    let mut page = settings().encode(7);
+    page[HEADER_SIZE] ^= 1;
+    assert_eq!(Settings::decode(&page), None);
+    assert_eq!(Settings::decode(&[0xff; PAGE_SIZE]), None);
+  }
+
+  /// A region of two sectors with records of the given sequence numbers at the given pages.
+  fn region(records: &[(usize, u32)]) -> Vec<u8> {
+    let mut region = vec![0xff; 2 * SECTOR_SIZE];
+    for &(page, sequence) in records {
+      let offset = page * PAGE_SIZE;
+      region[offset..offset + PAGE_SIZE].copy_from_slice(&Settings::default().encode(sequence));
+    }
+    region
+  }
+
+  #[test]
+  fn loads_the_newest_record() {
+    let (journal, settings) = Journal::load(&region(&[(0, 3), (1, 4), (2, 5)]));
+    assert_eq!(settings, Some(Settings::default()));
+    assert_eq!((journal.next, journal.sequence), (3, 6));
+
+    let (journal, settings) = Journal::load(&region(&[]));
+    assert_eq!(settings, None);
+    assert_eq!((journal.next, journal.sequence), (0, 0));
+  }
+
+  #[test]
+  fn loads_the_newest_record_after_wrapping_around() {
+    // the journal continued at the start of the region
+    let (journal, _) = Journal::load(&region(&[(0, 32), (30, 30), (31, 31)]));
+    assert_eq!((journal.next, journal.sequence), (1, 33));
+
+    // the sequence number continued at zero
+    let (journal, _) = Journal::load(&region(&[(4, u32::MAX - 1), (5, u32::MAX), (6, 0)]));
+    assert_eq!((journal.next, journal.sequence), (7, 1));
+  }
+
+  #[test]
+  fn appends_after_the_newest_record() {
+    let (mut journal, _) = Journal::load(&region(&[(14, 1), (15, 2)]));
+    let record = journal.append(&Settings::default());
+    // the next record starts the second sector, so it's erased first
+    assert_eq!((record.offset, record.erase), (16 * PAGE_SIZE, true));
+    assert_eq!(Settings::decode(&record.page).map(|(s, _)| s), Some(3));
+
+    // a page which isn't blank makes the journal continue with the next sector
+    let (journal, _) = Journal::load(&region(&[(3, 1), (4, 0)]));
+    assert_eq!(journal.next, 16);
+  }
+}
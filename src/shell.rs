@@ -0,0 +1,302 @@
+//! Text commands accepted over serial connections, independent of the transport.
+
+use core::fmt;
+
+use crate::{color::NormRgbw, control::ShowSpec, Fix32, ONE, ZERO};
+
+/// Longest line which is accepted, enough for a `geometry point` or a show with parameters.
+pub const MAX_LINE_LEN: usize = 128;
+
+pub const HELP: &str = "\
+commands:
+  show [<name> [<key>=<value> ...]]   show or change the show
+  shows                               list the shows
+  stop                                stop the show
+  color <#rrggbb[ww]>                 show a single color
+  brightness [<0..1>|<0..100>%]       show or change the brightness
+  calibrate [<setting> <value>]       show or change the calibration
+  power [<setting> <mA>]              show or change the power limit
+  transition [<style> [ms]]           show or change the transition between shows
+  geometry [<layout>]                 show or change the positions of the lights
+  key [<key> <action|none>|reset]     show or change the key mappings
+  status                              show everything above
+  help                                show this help
+  <key>                               send the remote action of the key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellError {
+  /// The line didn't fit into the buffer and was dropped.
+  LineTooLong,
+  UnknownCommand,
+  MissingArgument(&'static str),
+  /// The argument or the value of a setting was rejected, with the reason.
+  InvalidArgument(&'static str),
+}
+
+impl fmt::Display for ShellError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ShellError::LineTooLong => write!(f, "line too long"),
+      ShellError::UnknownCommand => write!(f, "unknown command, try `help`"),
+      ShellError::MissingArgument(name) => write!(f, "missing argument: {}", name),
+      ShellError::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+    }
+  }
+}
+
+/// Settings which are shown or changed by a command of the same name,
+/// the value is passed on to their `update` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+  Calibration,
+  Power,
+  Transition,
+  Geometry,
+  Keys,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellCommand<'a> {
+  Help,
+  Status,
+  /// `None` shows the current show.
+  Show(Option<ShowSpec>),
+  Shows,
+  Stop,
+  Color(NormRgbw),
+  /// `None` shows the current brightness.
+  Brightness(Option<Fix32>),
+  /// An empty value shows the setting.
+  Setting(Setting, &'a str),
+  /// A single character, handled like the remote action it's mapped to.
+  Key(&'a str),
+}
+
+impl<'a> ShellCommand<'a> {
+  /// Parses a line without the line ending, `None` for empty lines.
+  pub fn parse(line: &'a str) -> Option<Result<Self, ShellError>> {
+    let line = line.trim();
+    if line.is_empty() {
+      return None;
+    }
+    let (name, args) = match line.split_once(char::is_whitespace) {
+      Some((name, args)) => (name, args.trim()),
+      None => (line, ""),
+    };
+    let setting = |setting| Ok(ShellCommand::Setting(setting, args));
+    let command = match name {
+      "help" => Ok(ShellCommand::Help),
+      "status" => Ok(ShellCommand::Status),
+      "show" if args.is_empty() => Ok(ShellCommand::Show(None)),
+      "show" => Ok(ShellCommand::Show(Some(ShowSpec::new(args)))),
+      "shows" => Ok(ShellCommand::Shows),
+      "stop" => Ok(ShellCommand::Stop),
+      "color" if args.is_empty() => Err(ShellError::MissingArgument("color")),
+      "color" => {
+        NormRgbw::from_hex(args)
+          .map(ShellCommand::Color)
+          .ok_or(ShellError::InvalidArgument(
+            "expected `#rrggbb` or `#rrggbbww`",
+          ))
+      }
+      "brightness" if args.is_empty() => Ok(ShellCommand::Brightness(None)),
+      "brightness" => parse_brightness(args).map(|b| ShellCommand::Brightness(Some(b))),
+      "calibrate" => setting(Setting::Calibration),
+      "power" => setting(Setting::Power),
+      "transition" => setting(Setting::Transition),
+      "geometry" => setting(Setting::Geometry),
+      "key" => setting(Setting::Keys),
+      key if key.chars().count() == 1 && args.is_empty() => Ok(ShellCommand::Key(key)),
+      _ => Err(ShellError::UnknownCommand),
+    };
+    Some(command)
+  }
+}
+
+/// Parses `0.4` or `40%`.
+fn parse_brightness(arg: &str) -> Result<Fix32, ShellError> {
+  const ERROR: ShellError = ShellError::InvalidArgument("expected 0 to 1 or 0% to 100%");
+  let brightness = match arg.strip_suffix('%') {
+    Some(percent) => percent.trim().parse::<Fix32>().map_err(|_| ERROR)? / 100,
+    None => arg.parse::<Fix32>().map_err(|_| ERROR)?,
+  };
+  if (ZERO..=ONE).contains(&brightness) {
+    Ok(brightness)
+  } else {
+    Err(ERROR)
+  }
+}
+
+/// Collects received bytes into lines, with backspace for editing.
+pub struct LineBuffer {
+  line: [u8; MAX_LINE_LEN],
+  len: usize,
+  /// The line was returned, it's cleared with the next byte.
+  complete: bool,
+  /// Bytes are dropped until the end of the line.
+  overflow: bool,
+}
+
+impl Default for LineBuffer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LineBuffer {
+  pub const fn new() -> Self {
+    Self {
+      line: [0; MAX_LINE_LEN],
+      len: 0,
+      complete: false,
+      overflow: false,
+    }
+  }
+
+  /// Adds a received byte and echoes it to `echo`.
+  /// Returns the line without the line ending once it's complete.
+  pub fn push(&mut self, byte: u8, echo: &mut impl fmt::Write) -> Option<Result<&str, ShellError>> {
+    if self.complete {
+      self.complete = false;
+      self.len = 0;
+    }
+    match byte {
+      b'\r' | b'\n' => {
+        let _ = echo.write_str("\r\n");
+        self.complete = true;
+        if self.overflow {
+          self.overflow = false;
+          return Some(Err(ShellError::LineTooLong));
+        }
+        // lines which aren't valid UTF-8 are ignored like empty ones
+        Some(Ok(
+          core::str::from_utf8(&self.line[..self.len]).unwrap_or_default(),
+        ))
+      }
+      // backspace and delete
+      0x08 | 0x7f => {
+        if self.len > 0 && !self.overflow {
+          self.len -= 1;
+          let _ = echo.write_str("\x08 \x08");
+        }
+        None
+      }
+      _ if self.overflow => None,
+      _ if self.len == MAX_LINE_LEN => {
+        self.overflow = true;
+        None
+      }
+      _ => {
+        self.line[self.len] = byte;
+        self.len += 1;
+        if byte.is_ascii() {
+          let _ = echo.write_char(byte as char);
+        }
+        None
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::String;
+
+  use super::*;
+  use crate::nl;
+
+  #[test]
+  fn parses_commands() {
+    assert_eq!(ShellCommand::parse("  "), None);
+    assert_eq!(ShellCommand::parse("help"), Some(Ok(ShellCommand::Help)));
+    assert_eq!(
+      ShellCommand::parse(" show  fire   cooling=0.3 "),
+      Some(Ok(ShellCommand::Show(Some(ShowSpec::new(
+        "fire cooling=0.3"
+      )))))
+    );
+    assert_eq!(
+      ShellCommand::parse("color #ff0000"),
+      Some(Ok(ShellCommand::Color(NormRgbw::RED)))
+    );
+    assert_eq!(
+      ShellCommand::parse("brightness 50%"),
+      Some(Ok(ShellCommand::Brightness(Some(nl!(0.5)))))
+    );
+    assert_eq!(
+      ShellCommand::parse("power budget 2000"),
+      Some(Ok(ShellCommand::Setting(Setting::Power, "budget 2000")))
+    );
+    assert_eq!(ShellCommand::parse("+"), Some(Ok(ShellCommand::Key("+"))));
+  }
+
+  #[test]
+  fn rejects_invalid_commands() {
+    assert_eq!(
+      ShellCommand::parse("color"),
+      Some(Err(ShellError::MissingArgument("color")))
+    );
+    assert!(matches!(
+      ShellCommand::parse("brightness 2"),
+      Some(Err(ShellError::InvalidArgument(_)))
+    ));
+    assert_eq!(
+      ShellCommand::parse("dance"),
+      Some(Err(ShellError::UnknownCommand))
+    );
+  }
+
+  /// Pushes all bytes and returns the last completed line.
+  fn push(
+    buffer: &mut LineBuffer,
+    bytes: &[u8],
+    echo: &mut String,
+  ) -> Option<Result<String, ShellError>> {
+    let mut line = None;
+    for &byte in bytes {
+      if let Some(result) = buffer.push(byte, echo) {
+        line = Some(result.map(String::from));
+      }
+    }
+    line
+  }
+
+  #[test]
+  fn collects_lines() {
+    let mut buffer = LineBuffer::new();
+    let mut echo = String::new();
+    assert_eq!(push(&mut buffer, b"stoq", &mut echo), None);
+    assert_eq!(
+      push(&mut buffer, b"\x7fp\r", &mut echo),
+      Some(Ok("stop".into()))
+    );
+    assert_eq!(echo, "stoq\x08 \x08p\r\n");
+    assert_eq!(
+      push(&mut buffer, b"help\n", &mut echo),
+      Some(Ok("help".into()))
+    );
+  }
+
+  #[test]
+  fn drops_lines_which_are_too_long() {
+    let mut buffer = LineBuffer::new();
+    let mut echo = String::new();
+    let long = [b'x'; MAX_LINE_LEN + 1];
+    assert_eq!(push(&mut buffer, &long, &mut echo), None);
+    // editing doesn't help anymore
+    assert_eq!(
+      push(&mut buffer, b"\x7f\r", &mut echo),
+      Some(Err(ShellError::LineTooLong))
+    );
+    // the next line is accepted again
+    assert_eq!(
+      push(&mut buffer, b"stop\r", &mut echo),
+      Some(Ok("stop".into()))
+    );
+    let full = [b'x'; MAX_LINE_LEN];
+    assert_eq!(
+      push(&mut buffer, &[&full[..], b"\r"].concat(), &mut echo),
+      Some(Ok(String::from_utf8(full.to_vec()).unwrap()))
+    );
+  }
+}
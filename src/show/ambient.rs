@@ -0,0 +1,180 @@
+use core::str::FromStr;
+
+use crate::{
+  color::{NormHsv, NormRgbw},
+  geometry::Point,
+  nl,
+  noise::{self, Noise},
+  Fix32, ONE, ZERO,
+};
+
+use super::{adjust, Action, FrameContext, Micros, Show, Variants, FRAME_TIME};
+
+/// Longer frames are treated as this long, so the animation doesn't jump.
+const MAX_DELTA: Micros = 100_000;
+
+/// Slowly changing patterns for ambient lighting, all of them based on noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbientStyle {
+  /// Orange blobs in dark red.
+  Lava,
+  /// Curtains of green and purple.
+  Aurora,
+  /// Blue waves with foam on top.
+  Ocean,
+  /// Gradients of pale colors.
+  Pastel,
+}
+
+impl Variants for AmbientStyle {
+  const ALL: &'static [Self] = &[Self::Lava, Self::Aurora, Self::Ocean, Self::Pastel];
+}
+
+impl AmbientStyle {
+  /// Time it takes the pattern to change completely at a speed of 1.
+  fn period(self) -> Micros {
+    match self {
+      AmbientStyle::Lava => 8_000_000,
+      AmbientStyle::Aurora => 5_000_000,
+      AmbientStyle::Ocean => 3_000_000,
+      AmbientStyle::Pastel => 20_000_000,
+    }
+  }
+
+  /// Size of the pattern in units of the geometry, at a scale of 1.
+  fn size(self) -> Fix32 {
+    match self {
+      AmbientStyle::Lava => nl!(0.3),
+      AmbientStyle::Aurora => nl!(0.2),
+      AmbientStyle::Ocean => nl!(0.25),
+      AmbientStyle::Pastel => ONE,
+    }
+  }
+
+  fn color(self, noise: &Noise, p: Point, t: Fix32) -> NormRgbw {
+    match self {
+      AmbientStyle::Lava => {
+        let blob = smoothstep(nl!(-0.1), nl!(0.15), noise.fractal3(p.x, p.y, t, 2));
+        NormHsv::new(blob / 10, ONE, nl!(0.2) + blob * nl!(0.8)).into()
+      }
+      AmbientStyle::Aurora => {
+        // the curtains hang along the y axis, so they change slowly along it
+        let curtain = smoothstep(nl!(-0.1), nl!(0.3), noise.fractal3(p.x, p.y / 4, t, 3));
+        let hue = smoothstep(nl!(-0.3), nl!(0.3), noise.noise2(p.x / 4, t));
+        NormHsv::new(nl!(1) / 3 + hue * nl!(0.45), ONE, curtain).into()
+      }
+      AmbientStyle::Ocean => {
+        // the waves roll along the x axis
+        let wave = noise.fractal3(noise::wrap(p.x - t), p.y, t, 3);
+        let depth = smoothstep(nl!(-0.4), nl!(0.4), wave);
+        let water: NormRgbw = NormHsv::new(nl!(0.63), ONE, nl!(0.3)).into();
+        let crest: NormRgbw = NormHsv::new(nl!(0.5), nl!(0.8), nl!(0.9)).into();
+        let foam = smoothstep(nl!(0.3), nl!(0.5), wave);
+        water
+          .gradient(crest, depth)
+          .gradient(NormRgbw::RGB, foam / 2)
+      }
+      AmbientStyle::Pastel => {
+        let hue = (noise.noise3(p.x, p.y, t) * nl!(1.5) + ONE).frac();
+        NormHsv::new(hue, nl!(0.4), ONE).into()
+      }
+    }
+  }
+}
+
+impl FromStr for AmbientStyle {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "lava" => Ok(Self::Lava),
+      "aurora" => Ok(Self::Aurora),
+      "ocean" => Ok(Self::Ocean),
+      "pastel" => Ok(Self::Pastel),
+      _ => Err(()),
+    }
+  }
+}
+
+/// 0 below `low`, 1 above `high` and smooth in between.
+fn smoothstep(low: Fix32, high: Fix32, x: Fix32) -> Fix32 {
+  let t = ((x - low) / (high - low)).clamp(ZERO, ONE);
+  t * t * (nl!(3) - 2 * t)
+}
+
+/// Noise sampled at the positions of the lights, moving through time.
+pub struct AmbientShow {
+  style: AmbientStyle,
+  speed: Fix32,
+  /// Higher values give larger structures.
+  scale: Fix32,
+  noise: Option<Noise>,
+  /// Position along the time axis of the noise.
+  t: Fix32,
+  controllable: Controllable,
+}
+
+impl AmbientShow {
+  pub fn new(style: AmbientStyle, speed: Fix32, scale: Fix32) -> Self {
+    Self {
+      style,
+      speed: speed.clamp(ZERO, nl!(100)),
+      scale: scale.clamp(nl!(0.01), nl!(100)),
+      noise: None,
+      t: ZERO,
+      controllable: Controllable::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Controllable {
+  #[default]
+  Speed,
+  Scale,
+  Style,
+}
+
+impl Variants for Controllable {
+  const ALL: &'static [Self] = &[Self::Speed, Self::Scale, Self::Style];
+}
+
+impl AmbientShow {
+  fn handle_action(&mut self, action: Action) {
+    let Some((controllable, up)) = adjust(&mut self.controllable, action) else {
+      return;
+    };
+    let factor = if up { nl!(1.25) } else { nl!(0.8) };
+    match controllable {
+      Controllable::Speed => self.speed = (self.speed * factor).clamp(nl!(0.05), nl!(100)),
+      Controllable::Scale => self.scale = (self.scale * factor).clamp(nl!(0.01), nl!(100)),
+      Controllable::Style => self.style = self.style.next(),
+    }
+  }
+}
+
+impl Show for AmbientShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let Some(action) = ctx.action {
+      self.handle_action(action);
+    }
+    let noise = self.noise.get_or_insert_with(|| Noise::new(ctx.seed));
+
+    let step =
+      Fix32::from_bits((ctx.delta.min(MAX_DELTA) * (1 << 16) / self.style.period()) as i32);
+    self.t = noise::wrap(self.t + step * self.speed);
+
+    let size = self.style.size() * self.scale;
+    let style = self.style;
+    let t = self.t;
+    ctx.geometry.sample(frame, |point| {
+      let p = Point::new(
+        noise::wrap(point.x.saturating_div(size)),
+        noise::wrap(point.y.saturating_div(size)),
+        noise::wrap(point.z.saturating_div(size)),
+      );
+      style.color(noise, p, t)
+    });
+    FRAME_TIME
+  }
+}
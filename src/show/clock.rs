@@ -1,96 +1,71 @@
 use core::ops::Add;
 
-use cortex_m::prelude::*;
+use crate::{color::NormRgbw, segment::Segment};
 
-use crate::{
-  light::{
-    color::NormRgbw,
-    controller::{ColorMemoryController, MemoryController, MemoryControllerExt},
-    Lights,
-  },
-  return_cancel,
-  util::AsmDelay,
-};
-
-use super::Show;
+use super::{FrameContext, Micros, Show};
 
 #[derive(Default)]
 pub struct RgbClockShow {
   with_seconds: bool,
 }
 impl Show for RgbClockShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    mut asm_delay: AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    const N24: usize = Lights::N / 24;
-    const N60: usize = Lights::N / 60;
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    const START: u64 = 19 * 60 * 60 + 12 * 60;
+    const TICK: Micros = 500_000;
+
+    let n24 = frame.len() / 24;
+    let n60 = frame.len() / 60;
 
-    for hour in 19..24 {
-      for minute in 12..60 {
-        for second in 0..60 {
-          for l in 0..Lights::N {
-            let mut color = NormRgbw::NONE;
-            if l < (hour + 1) * N24 {
-              color = color.add(NormRgbw::RED);
-            }
-            if l < (minute + 1) * N60 {
-              color = color.add(NormRgbw::GREEN);
-            }
-            if self.with_seconds && l < (second + 1) * N60 {
-              color = color.add(NormRgbw::BLUE);
-            }
-            ctrl.set(l, color);
-          }
-          ctrl.display(config);
-          asm_delay.delay_ms(500);
-          return_cancel!(cancel);
-        }
+    let time = (START + ctx.time / TICK) % (24 * 60 * 60);
+    let hour = (time / (60 * 60)) as usize;
+    let minute = (time / 60 % 60) as usize;
+    let second = (time % 60) as usize;
+
+    for (l, led) in frame.iter_mut().enumerate() {
+      let mut color = NormRgbw::NONE;
+      if l < (hour + 1) * n24 {
+        color = color.add(NormRgbw::RED);
+      }
+      if l < (minute + 1) * n60 {
+        color = color.add(NormRgbw::GREEN);
       }
+      if self.with_seconds && l < (second + 1) * n60 {
+        color = color.add(NormRgbw::BLUE);
+      }
+      *led = color;
     }
+    TICK - ctx.time % TICK
   }
 }
 
 #[derive(Default)]
 pub struct SeparatedClockShow;
 impl Show for SeparatedClockShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    mut asm_delay: AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    let cn = Lights::N / 2;
-    let n12 = cn / 12;
-    let n60 = cn / 60;
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    const TICK: Micros = 100_000;
 
-    for hour in 0..12 {
-      for minute in 0..60 {
-        ctrl.set_all(NormRgbw::NONE);
-        ctrl.set_range(0..(n12 * hour), NormRgbw::RED);
-        ctrl.set_range(cn..(cn + n60 * minute), NormRgbw::GREEN);
+    let hours = Segment::new("hours", 0, frame.len() / 2);
+    let minutes = hours.next("minutes", frame.len() / 2);
+    let n12 = hours.len / 12;
+    let n60 = minutes.len / 60;
 
-        for i in 0..12 {
-          let l = i * n12;
-          let color = if i % 3 == 0 {
-            NormRgbw::BLUE
-          } else {
-            NormRgbw::BLUE.mix(NormRgbw::WHITE)
-          };
-          ctrl.set(l, color);
-          ctrl.set(cn + l, color);
-        }
+    let step = ctx.time / TICK;
+    let hour = (step / 60 % 12) as usize;
+    let minute = (step % 60) as usize;
 
-        ctrl.display(config);
-        asm_delay.delay_ms(100);
-        return_cancel!(cancel);
-      }
+    frame.fill(NormRgbw::NONE);
+    hours.fill_range(frame, 0..(n12 * hour), NormRgbw::RED);
+    minutes.fill_range(frame, 0..(n60 * minute), NormRgbw::GREEN);
+
+    for i in 0..12 {
+      let color = if i % 3 == 0 {
+        NormRgbw::BLUE
+      } else {
+        NormRgbw::BLUE.mix(NormRgbw::WHITE)
+      };
+      hours.set(frame, i * n12, color);
+      minutes.set(frame, i * n12, color);
     }
+    TICK - ctx.time % TICK
   }
 }
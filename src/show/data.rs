@@ -1,13 +1,6 @@
-use crate::{
-  light::{
-    color::NormRgbw,
-    controller::{ColorMemoryController, MemoryController, MemoryControllerExt},
-    Lights,
-  },
-  util::AsmDelay,
-};
+use crate::color::NormRgbw;
 
-use super::Show;
+use super::{FrameContext, Micros, Show, FRAME_TIME};
 
 pub struct ByteShow<'a>(&'a [u8]);
 impl<'a> ByteShow<'a> {
@@ -16,21 +9,14 @@ impl<'a> ByteShow<'a> {
   }
 }
 impl Show for ByteShow<'_> {
-  fn run(
-    &mut self,
-    _cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    _asm_delay: AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
+  fn render(&mut self, _ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
     let nbytes = self.0.len();
     let nbits = 8 * nbytes;
     let nspace = (8 + 1) * nbytes;
-    let _lper_bit = Lights::N / nbits;
-    let lper_space = Lights::N / nspace;
+    let _lper_bit = frame.len() / nbits;
+    let lper_space = frame.len() / nspace;
 
-    ctrl.set_all(NormRgbw::NONE);
+    frame.fill(NormRgbw::NONE);
     for ispace in 0..nspace {
       let is_seperator = ispace % 9 == 8;
       let color = if is_seperator {
@@ -45,8 +31,8 @@ impl Show for ByteShow<'_> {
           NormRgbw::RED
         }
       };
-      ctrl.set_range((ispace * lper_space)..((ispace + 1) * lper_space), color);
-      ctrl.display(config);
+      frame[(ispace * lper_space)..((ispace + 1) * lper_space)].fill(color);
     }
+    FRAME_TIME
   }
 }
@@ -1,70 +1,55 @@
-use arclib::{nl, ONE};
-use embedded_hal::blocking::delay::DelayMs;
-
 use crate::{
-  light::{
-    color::{NormHsv, NormRgbw},
-    controller::{ColorMemoryController, MemoryController, MemoryControllerExt},
-    Lights,
-  },
-  util::AsmDelay,
+  color::{NormHsv, NormRgbw},
+  nl, ONE, ZERO,
 };
 
-use super::{GradientShow, Show};
-use crate::return_cancel;
+use super::{FrameContext, GradientShow, Micros, Show};
 
 #[derive(Default)]
-pub struct DemoShow;
+pub struct DemoShow {
+  step: usize,
+}
 
 impl Show for DemoShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    mut asm_delay: AsmDelay,
-    remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    ctrl.set_all(NormRgbw::NONE);
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    const HUE_STEPS: usize = 360;
 
-    loop {
-      // all colors loading bar
-      for color in NormRgbw::STANDARD_PALETTE {
-        for l in 0..Lights::N {
-          ctrl.set(l, color);
-          ctrl.display(config);
-          asm_delay.delay_ms(2);
-          return_cancel!(cancel);
-        }
-        for l in 0..Lights::N {
-          ctrl.set(l, NormRgbw::NONE);
-          ctrl.display(config);
-          asm_delay.delay_ms(2);
-          return_cancel!(cancel);
-        }
-        ctrl.set_all(NormRgbw::NONE);
-        ctrl.display(config);
-      }
-      for shift in 0..360u32 {
-        let shiftf = nl!(shift) / nl!(360u32 - 1);
-        for l in 0..Lights::N {
-          let lf = nl!(l) / nl!(Lights::N - 1);
-          let hue = (shiftf + lf).rem_euclid(ONE);
-          let color = NormHsv::new(hue, ONE, ONE);
-          ctrl.set(l, color.into());
-        }
-        ctrl.display(config);
-        asm_delay.delay_ms(16);
-        return_cancel!(cancel);
+    let n = frame.len();
+    // every color gets loaded and unloaded led by led
+    let bar_steps = 2 * n;
+    let bars_steps = NormRgbw::STANDARD_PALETTE.len() * bar_steps;
+
+    let step = self.step;
+    self.step += 1;
+
+    if step == 0 {
+      frame.fill(NormRgbw::NONE);
+    }
+
+    // all colors loading bar
+    if step < bars_steps {
+      let color = NormRgbw::STANDARD_PALETTE[step / bar_steps];
+      let l = step % bar_steps;
+      if l < n {
+        frame[l] = color;
+      } else {
+        frame[l - n] = NormRgbw::NONE;
       }
+      return 2_000;
+    }
 
-      GradientShow::new(NormRgbw::RED, NormRgbw::YELLOW).run(
-        cancel,
-        ctrl,
-        asm_delay,
-        remote_input,
-        config,
-      );
+    let shift = step - bars_steps;
+    if shift < HUE_STEPS {
+      let shiftf = nl!(shift) / nl!(HUE_STEPS - 1);
+      for (l, led) in frame.iter_mut().enumerate() {
+        let lf = if n <= 1 { ZERO } else { nl!(l) / nl!(n - 1) };
+        let hue = (shiftf + lf).rem_euclid(ONE);
+        *led = NormHsv::new(hue, ONE, ONE).into();
+      }
+      return 16_000;
     }
+
+    self.step = 0;
+    GradientShow::new(NormRgbw::RED, NormRgbw::YELLOW).render(ctx, frame)
   }
 }
@@ -0,0 +1,182 @@
+use alloc::{vec, vec::Vec};
+use core::str::FromStr;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+  color::{NormHsv, NormRgbw},
+  nl, Fix32, ONE, ZERO,
+};
+
+use super::{adjust, Action, FrameContext, Micros, Show, Variants, FRAME_TIME};
+
+/// Time of a step of the simulation, independent of the frame rate.
+const STEP: Micros = 15_000;
+/// More steps are skipped after a long frame, so the show doesn't stall.
+const MAX_STEPS: Micros = 10;
+/// Sparks ignite among this many lights at the start of the strip.
+const SPARK_ZONE: usize = 7;
+
+/// Colors from the coolest to the hottest flames, which are white for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FirePalette {
+  /// Red to yellow.
+  #[default]
+  Classic,
+  /// Blue to cyan.
+  Blue,
+  /// Green to yellow.
+  Green,
+}
+
+impl Variants for FirePalette {
+  const ALL: &'static [Self] = &[Self::Classic, Self::Blue, Self::Green];
+}
+
+impl FirePalette {
+  /// Hues of the coolest and the hottest colored flames.
+  fn hues(self) -> (Fix32, Fix32) {
+    match self {
+      FirePalette::Classic => (ZERO, nl!(1) / 6),
+      FirePalette::Blue => (nl!(2) / 3, nl!(1) / 2),
+      FirePalette::Green => (nl!(1) / 3, nl!(1) / 6),
+    }
+  }
+
+  /// Black to the first hue, to the second hue, to white.
+  pub fn color(self, heat: Fix32) -> NormRgbw {
+    let (cool, hot) = self.hues();
+    let third = ONE / 3;
+    let hsv = if heat < third {
+      NormHsv::new(cool, ONE, heat * 3)
+    } else if heat < 2 * third {
+      NormHsv::new(cool + (heat - third) * 3 * (hot - cool), ONE, ONE)
+    } else {
+      NormHsv::new(hot, (ONE - (heat - 2 * third) * 3).max(ZERO), ONE)
+    };
+    hsv.into()
+  }
+}
+
+impl FromStr for FirePalette {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "classic" => Ok(Self::Classic),
+      "blue" => Ok(Self::Blue),
+      "green" => Ok(Self::Green),
+      _ => Err(()),
+    }
+  }
+}
+
+/// Flames rising from the start of the strip.
+/// Every light has a heat, which cools down, drifts up the strip and is increased by random sparks.
+pub struct FireShow {
+  /// Between 0 and 1, higher values give shorter flames.
+  cooling: Fix32,
+  /// Chance of a new spark every step.
+  sparking: Fix32,
+  palette: FirePalette,
+  heat: Vec<Fix32>,
+  rng: Option<SmallRng>,
+  /// Time which wasn't simulated yet.
+  pending: Micros,
+  controllable: Controllable,
+}
+
+impl Default for FireShow {
+  fn default() -> Self {
+    Self::new(nl!(0.55), nl!(0.47), FirePalette::default())
+  }
+}
+
+impl FireShow {
+  pub fn new(cooling: Fix32, sparking: Fix32, palette: FirePalette) -> Self {
+    Self {
+      cooling: cooling.clamp(ZERO, ONE),
+      sparking: sparking.clamp(ZERO, ONE),
+      palette,
+      heat: Vec::new(),
+      rng: None,
+      pending: 0,
+      controllable: Controllable::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Controllable {
+  #[default]
+  Cooling,
+  Sparking,
+  Palette,
+}
+
+impl Variants for Controllable {
+  const ALL: &'static [Self] = &[Self::Cooling, Self::Sparking, Self::Palette];
+}
+
+impl FireShow {
+  fn handle_action(&mut self, action: Action) {
+    let Some((controllable, up)) = adjust(&mut self.controllable, action) else {
+      return;
+    };
+    let delta = if up { nl!(0.05) } else { nl!(-0.05) };
+    match controllable {
+      Controllable::Cooling => self.cooling = (self.cooling + delta).clamp(ZERO, ONE),
+      Controllable::Sparking => self.sparking = (self.sparking + delta).clamp(ZERO, ONE),
+      Controllable::Palette => self.palette = self.palette.next(),
+    }
+  }
+}
+
+/// Advances the simulation by a single step.
+fn step(heat: &mut [Fix32], rng: &mut SmallRng, cooling: Fix32, sparking: Fix32) {
+  let n = heat.len();
+  if n == 0 {
+    return;
+  }
+
+  // shorter strips cool down faster, so the flames have the same shape
+  let max_cooling = (cooling * 1000 / nl!(n) + nl!(2)) / 255;
+  for heat in heat.iter_mut() {
+    *heat = (*heat - nl!(rng.gen::<f32>()) * max_cooling).max(ZERO);
+  }
+
+  for k in (2..n).rev() {
+    heat[k] = (heat[k - 1] + 2 * heat[k - 2]) / 3;
+  }
+
+  if nl!(rng.gen::<f32>()) < sparking {
+    let y = rng.gen_range(0..n.min(SPARK_ZONE));
+    heat[y] = (heat[y] + nl!(rng.gen_range(0.6f32..1.0))).min(ONE);
+  }
+}
+
+impl Show for FireShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let Some(action) = ctx.action {
+      self.handle_action(action);
+    }
+    if self.heat.len() != frame.len() {
+      self.heat = vec![ZERO; frame.len()];
+    }
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+
+    self.pending += ctx.delta;
+    let steps = (self.pending / STEP).min(MAX_STEPS);
+    self.pending %= STEP;
+    for _ in 0..steps {
+      step(&mut self.heat, rng, self.cooling, self.sparking);
+    }
+
+    for (led, &heat) in frame.iter_mut().zip(&self.heat) {
+      *led = self.palette.color(heat);
+    }
+    FRAME_TIME
+  }
+}
@@ -1,15 +1,6 @@
-use arclib::nl;
+use crate::{color::NormRgbw, nl, ZERO};
 
-use crate::{
-  light::{
-    color::NormRgbw,
-    controller::{ColorMemoryController, MemoryController},
-    Lights,
-  },
-  util::AsmDelay,
-};
-
-use super::Show;
+use super::{FrameContext, Micros, Show, FRAME_TIME};
 
 pub struct GradientShow {
   from: NormRgbw,
@@ -21,18 +12,12 @@ impl GradientShow {
   }
 }
 impl Show for GradientShow {
-  fn run(
-    &mut self,
-    _cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    _asm_delay: AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    for l in 0..Lights::N {
-      let lf = nl!(l) / nl!(Lights::N - 1);
-      ctrl.set(l, self.from.gradient(self.to, lf));
+  fn render(&mut self, _ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let n = frame.len();
+    for (l, led) in frame.iter_mut().enumerate() {
+      let lf = if n <= 1 { ZERO } else { nl!(l) / nl!(n - 1) };
+      *led = self.from.perceptual_gradient(self.to, lf);
     }
-    ctrl.display(config);
+    FRAME_TIME
   }
 }
@@ -0,0 +1,72 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+  color::{BlendMode, NormRgbw},
+  Fix32, ONE, ZERO,
+};
+
+use super::{FrameContext, Micros, Nested, Show, FRAME_TIME};
+
+/// A show on top of the layers below it.
+pub struct Layer {
+  show: Box<dyn Show + Send>,
+  mode: BlendMode,
+  /// How much of the blended result is used, between 0 and 1.
+  opacity: Fix32,
+}
+
+impl Layer {
+  pub fn new(show: Box<dyn Show + Send>, mode: BlendMode, opacity: Fix32) -> Self {
+    Self {
+      show,
+      mode,
+      opacity: opacity.clamp(ZERO, ONE),
+    }
+  }
+
+  /// A layer which covers everything below it.
+  pub fn opaque(show: Box<dyn Show + Send>) -> Self {
+    Self::new(show, BlendMode::Normal, ONE)
+  }
+}
+
+/// Renders every layer into its own frame and composites them, starting at the bottom.
+/// Remote actions are forwarded to all layers.
+pub struct LayersShow {
+  layers: Vec<(Nested, BlendMode, Fix32)>,
+}
+
+impl LayersShow {
+  /// `layers` are ordered from the bottom to the top.
+  pub fn new(layers: Vec<Layer>) -> Self {
+    let layers = layers
+      .into_iter()
+      .map(|layer| (Nested::new(layer.show), layer.mode, layer.opacity))
+      .collect();
+    Self { layers }
+  }
+}
+
+impl Show for LayersShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let mut changed = false;
+    for (layer, _, _) in &mut self.layers {
+      changed |= layer.render(ctx, frame.len());
+    }
+
+    if changed {
+      frame.fill(NormRgbw::NONE);
+      for (layer, mode, opacity) in &self.layers {
+        for (led, &top) in frame.iter_mut().zip(&layer.frame) {
+          *led = led.blend(top, *mode, *opacity);
+        }
+      }
+    }
+    self
+      .layers
+      .iter()
+      .map(|(layer, _, _)| layer.until_next_frame(ctx.time))
+      .min()
+      .unwrap_or(FRAME_TIME)
+  }
+}
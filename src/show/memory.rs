@@ -0,0 +1,62 @@
+use alloc::{
+  string::{String, ToString},
+  vec::Vec,
+};
+use core::cell::{Cell, RefCell};
+
+/// Values which shows keep across restarts, like the high score of a game.
+/// Every show uses its own keys, the driver stores the values with the settings
+/// whenever they changed.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+  values: RefCell<Vec<(String, u32)>>,
+  changed: Cell<bool>,
+}
+
+impl Memory {
+  pub fn get(&self, key: &str) -> Option<u32> {
+    let values = self.values.borrow();
+    values
+      .iter()
+      .find(|(k, _)| k == key)
+      .map(|&(_, value)| value)
+  }
+
+  pub fn set(&self, key: &str, value: u32) {
+    let mut values = self.values.borrow_mut();
+    match values.iter_mut().find(|(k, _)| k == key) {
+      Some((_, v)) if *v == value => return,
+      Some((_, v)) => *v = value,
+      None => values.push((key.to_string(), value)),
+    }
+    self.changed.set(true);
+  }
+
+  /// Whether a value changed since the last call.
+  pub fn take_changed(&self) -> bool {
+    self.changed.replace(false)
+  }
+
+  /// All values in the order they were first set.
+  pub fn values(&self) -> Vec<(String, u32)> {
+    self.values.borrow().clone()
+  }
+}
+
+impl FromIterator<(String, u32)> for Memory {
+  fn from_iter<I: IntoIterator<Item = (String, u32)>>(iter: I) -> Self {
+    Self {
+      values: RefCell::new(iter.into_iter().collect()),
+      changed: Cell::new(false),
+    }
+  }
+}
+
+/// Memories with the same values are equal, whether they changed or not.
+impl PartialEq for Memory {
+  fn eq(&self, other: &Self) -> bool {
+    *self.values.borrow() == *other.values.borrow()
+  }
+}
+
+impl Eq for Memory {}
@@ -1,120 +1,249 @@
-use crate::{
-  app::{
-    self,
-    show_task::{self, SharedResources},
-  },
-  light::{controller::ColorMemoryController, Lights, LightsPin},
-  util::AsmDelay,
-};
-
-use rp_pico::{
-  hal::{self, clocks::ClockSource},
-  pac,
-};
-use rtic::Mutex;
+use alloc::{boxed::Box, vec::Vec};
 
+use crate::{color::NormRgbw, control::number_from_action, geometry::Geometry};
+
+pub mod ambient;
 pub mod clock;
 pub mod data;
 pub mod demo;
+pub mod fire;
 pub mod gradient;
+pub mod layers;
+pub mod memory;
 pub mod null;
+pub mod particles;
 pub mod quick;
+pub mod rainbow;
 pub mod random;
 pub mod snake;
 pub mod spotlight;
+pub mod sweep;
+pub mod transition;
+pub mod twinkle;
 pub mod uniform;
+pub mod white;
+pub mod zones;
 
+pub use ambient::{AmbientShow, AmbientStyle};
 pub use clock::{RgbClockShow, SeparatedClockShow};
 pub use data::ByteShow;
 pub use demo::DemoShow;
+pub use fire::{FirePalette, FireShow};
 pub use gradient::GradientShow;
+pub use layers::{Layer, LayersShow};
+pub use memory::Memory;
 pub use null::NullShow;
+pub use particles::{BouncingBallsShow, CometShow, FireworksShow};
 pub use quick::QuickShow;
+pub use rainbow::RainbowShow;
 pub use random::RandomShow;
 pub use snake::SnakeShow;
 pub use spotlight::SpotlightShow;
+pub use sweep::SweepShow;
+pub use transition::{Transition, TransitionShow, TransitionStyle};
+pub use twinkle::{TwinklePalette, TwinkleShow};
 pub use uniform::UniformShow;
+pub use white::WhiteShow;
+pub use zones::{Layout, ZonesShow};
+
+pub use infrared::remotecontrol::Action;
+
+/// Time in microseconds.
+pub type Micros = u64;
+
+/// Frame time for shows which want to be redrawn continuously.
+/// Roughly the time it takes to send a full frame to the strip.
+pub const FRAME_TIME: Micros = 10_000;
 
-pub struct ShowTask {
-  lights: Lights,
-  asm_delay: AsmDelay,
+/// Everything a show knows about the outside world while rendering a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameContext<'a> {
+  /// Time since the show was started.
+  pub time: Micros,
+  /// Time since the previous frame was rendered.
+  pub delta: Micros,
+  /// Remote input which arrived since the previous frame.
+  pub action: Option<Action>,
+  /// Seed for shows which need randomness.
+  /// Stays the same for the whole lifetime of a show.
+  pub seed: u64,
+  /// Positions of the lights of the frame.
+  pub geometry: &'a Geometry,
+  /// Values which survive restarts.
+  pub memory: &'a Memory,
 }
 
-impl ShowTask {
-  pub fn init(
-    lights_pin: LightsPin,
-    pio0: pac::PIO0,
-    sys_clock: &hal::clocks::SystemClock,
-    resets: &mut pac::RESETS,
-  ) -> Self {
-    let lights = Lights::init(pio0, resets, sys_clock.get_freq().0 as f32, lights_pin);
-    let asm_delay = AsmDelay::new(sys_clock.get_freq().0);
+/// A hardware independent show.
+///
+/// The driver repeatedly asks the show to render a frame and displays it.
+/// `frame` still contains the previously rendered frame, so shows can
+/// update it incrementally.
+pub trait Show {
+  /// Renders the next frame into `frame`.
+  /// Returns the time until the next frame should be rendered.
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros;
+}
 
-    show_task::spawn().unwrap();
+/// A fixed set of choices, like the palettes of a show or its parameters adjusted with the remote.
+pub trait Variants: Copy + PartialEq + 'static {
+  /// In the order they are cycled through and selected with the number keys, starting with One.
+  const ALL: &'static [Self];
 
-    Self { lights, asm_delay }
+  /// The variant after `self`, the first one after the last one.
+  #[must_use]
+  fn next(self) -> Self {
+    let i = Self::ALL
+      .iter()
+      .position(|&v| v == self)
+      .unwrap_or_default();
+    Self::ALL[(i + 1) % Self::ALL.len()]
   }
 }
 
-pub fn show_task(ctx: show_task::Context) {
-  let ShowTask { lights, asm_delay } = ctx.local.show_task;
-  let SharedResources {
-    mut show_cancellation_token,
-    mut config,
-    mut remote_input,
-  } = ctx.shared;
-  let show_take = config.lock(|s| s.show.take());
-  if let Some(mut show) = show_take {
-    let mut ctrl = ColorMemoryController::new(lights, *asm_delay);
-
-    show_cancellation_token.lock(|token| token.reset());
-    Show::run(
-      show.as_mut(),
-      &mut show_cancellation_token,
-      &mut ctrl,
-      *asm_delay,
-      &mut remote_input,
-      &mut config,
-    );
-  }
-  show_task::spawn().unwrap();
+/// Remote control of shows with several parameters.
+/// The number keys select the parameter, Prev and Next decrease and increase it.
+/// Returns the selected parameter and whether to increase it, if it's adjusted.
+pub fn adjust<P: Variants>(selected: &mut P, action: Action) -> Option<(P, bool)> {
+  let up = match action {
+    Action::Prev => false,
+    Action::Next => true,
+    action => {
+      let i = number_from_action(action)?.checked_sub(1)?;
+      if let Some(&parameter) = P::ALL.get(i) {
+        *selected = parameter;
+      }
+      return None;
+    }
+  };
+  Some((*selected, up))
 }
 
-pub trait Show {
-  fn run(
-    &mut self,
-    cancel: &mut app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    asm_delay: AsmDelay,
-    remote_input: &mut app::shared_resources::remote_input_lock,
-    config: &mut app::shared_resources::config_lock,
-  );
+/// A show inside of a show which combines several of them.
+/// Renders into its own frame, whenever it asks for it.
+struct Nested {
+  show: Box<dyn Show + Send>,
+  frame: Vec<NormRgbw>,
+  last_frame: Micros,
+  next_frame: Micros,
 }
 
-#[derive(Default)]
-pub struct ShowCancellationToken(bool);
+impl Nested {
+  fn new(show: Box<dyn Show + Send>) -> Self {
+    Self {
+      show,
+      frame: Vec::new(),
+      last_frame: 0,
+      next_frame: 0,
+    }
+  }
 
-impl ShowCancellationToken {
-  pub fn is_requested(&self) -> bool {
-    self.0
+  /// Whether the show wants to render or there is an action for it.
+  fn is_due(&self, ctx: &FrameContext) -> bool {
+    ctx.time >= self.next_frame || ctx.action.is_some()
   }
 
-  pub fn request(&mut self) {
-    self.0 = true;
+  /// Renders a frame of `len` lights if it's due.
+  /// Returns whether it rendered.
+  fn render(&mut self, ctx: &FrameContext, len: usize) -> bool {
+    if !self.is_due(ctx) {
+      return false;
+    }
+    self.frame.resize(len, NormRgbw::NONE);
+    let ctx = FrameContext {
+      delta: ctx.time - self.last_frame,
+      ..*ctx
+    };
+    self.last_frame = ctx.time;
+    self.next_frame = ctx.time + self.show.render(&ctx, &mut self.frame);
+    true
   }
 
-  fn reset(&mut self) {
-    self.0 = false;
+  fn until_next_frame(&self, time: Micros) -> Micros {
+    self.next_frame.saturating_sub(time)
   }
 }
 
-#[macro_export]
-macro_rules! return_cancel {
-  ($cancellation_token_lock:ident) => {{
-    if ::rtic::Mutex::lock($cancellation_token_lock, |cancellation_token| {
-      cancellation_token.is_requested()
-    }) {
-      return;
-    }
-  }};
+#[cfg(test)]
+mod tests {
+  use alloc::{boxed::Box, vec, vec::Vec};
+
+  use super::*;
+  use crate::segment::Segment;
+
+  fn render(show: &mut dyn Show, len: usize) -> Vec<NormRgbw> {
+    let geometry = Geometry::linear(len);
+    let memory = Memory::default();
+    let ctx = FrameContext {
+      time: 0,
+      delta: 0,
+      action: None,
+      seed: 0,
+      geometry: &geometry,
+      memory: &memory,
+    };
+    let mut frame = vec![NormRgbw::NONE; len];
+    show.render(&ctx, &mut frame);
+    frame
+  }
+
+  #[test]
+  fn gradient_goes_from_start_to_end() {
+    let frame = render(&mut GradientShow::new(NormRgbw::RED, NormRgbw::BLUE), 5);
+    assert_eq!(frame[0], NormRgbw::RED);
+    assert_eq!(frame[4], NormRgbw::BLUE);
+    assert_eq!(
+      render(&mut GradientShow::new(NormRgbw::RED, NormRgbw::BLUE), 1),
+      [NormRgbw::RED]
+    );
+  }
+
+  #[test]
+  fn zones_render_into_their_segments() {
+    let first = Segment::new("first", 0, 2);
+    let mut show = ZonesShow::new(vec![
+      (first, Box::new(UniformShow::new(NormRgbw::RED)) as _),
+      (
+        first.next("second", 2).reversed(),
+        Box::new(GradientShow::new(NormRgbw::GREEN, NormRgbw::BLUE)),
+      ),
+    ]);
+    assert_eq!(
+      render(&mut show, 5),
+      [
+        NormRgbw::RED,
+        NormRgbw::RED,
+        NormRgbw::BLUE,
+        NormRgbw::GREEN,
+        NormRgbw::NONE
+      ]
+    );
+  }
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  enum Parameter {
+    A,
+    B,
+  }
+
+  impl Variants for Parameter {
+    const ALL: &'static [Self] = &[Self::A, Self::B];
+  }
+
+  #[test]
+  fn adjust_selects_with_the_number_keys() {
+    let mut selected = Parameter::A;
+    assert_eq!(
+      adjust(&mut selected, Action::Next),
+      Some((Parameter::A, true))
+    );
+    assert_eq!(adjust(&mut selected, Action::Two), None);
+    assert_eq!(
+      adjust(&mut selected, Action::Prev),
+      Some((Parameter::B, false))
+    );
+    // there is no third parameter
+    assert_eq!(adjust(&mut selected, Action::Three), None);
+    assert_eq!(selected, Parameter::B);
+    assert_eq!(selected.next(), Parameter::A);
+  }
 }
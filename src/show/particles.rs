@@ -0,0 +1,226 @@
+use alloc::vec::Vec;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+  color::{NormHsv, NormRgbw},
+  nl,
+  particle::{Edges, Particle, Particles},
+  Fix32, ONE, ZERO,
+};
+
+use super::{FrameContext, Micros, Show, FRAME_TIME};
+
+/// Balls falling down to the start of the strip and bouncing back up.
+/// Once a ball comes to rest it's thrown up again.
+pub struct BouncingBallsShow {
+  count: usize,
+  balls: Particles,
+  rng: Option<SmallRng>,
+  /// Length of the strip the balls were thrown on.
+  len: usize,
+}
+
+impl Default for BouncingBallsShow {
+  fn default() -> Self {
+    Self::new(3)
+  }
+}
+
+impl BouncingBallsShow {
+  pub fn new(count: usize) -> Self {
+    Self {
+      count: count.clamp(1, 20),
+      balls: Particles::new(Edges::Bounce(nl!(0.8))),
+      rng: None,
+      len: 0,
+    }
+  }
+}
+
+impl Show for BouncingBallsShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let n = frame.len();
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+    // a ball dropped from the end of the strip hits the start after a second
+    let gravity = -nl!(2 * n);
+
+    if self.len != n {
+      self.len = n;
+      self.balls.clear();
+      for i in 0..self.count {
+        let color = NormHsv::new(nl!(i) / nl!(self.count), ONE, ONE).into();
+        // dropped from different heights, so they don't bounce in sync
+        let height = nl!(n.max(1) - 1) * nl!(self.count - i) / nl!(self.count);
+        let ball = Particle::new(height, color)
+          .with_gravity(gravity)
+          .with_damping(nl!(rng.gen_range(0.0f32..0.2)));
+        self.balls.spawn(ball);
+      }
+    }
+
+    self.balls.step(ctx.delta, n);
+    for ball in self.balls.iter_mut() {
+      let resting = ball.pos < ONE && ball.vel.abs() < nl!(n) / 10;
+      if resting {
+        // rising for `t` seconds reaches a height of `n * t^2`
+        ball.vel = -gravity * nl!(rng.gen_range(0.6f32..1.0));
+      }
+    }
+
+    frame.fill(NormRgbw::NONE);
+    self.balls.render(frame);
+    FRAME_TIME
+  }
+}
+
+/// Rockets rising from the start of the strip and bursting into sparks.
+pub struct FireworksShow {
+  rockets: Particles,
+  sparks: Particles,
+  rng: Option<SmallRng>,
+  /// Time until the next rocket is launched.
+  next_launch: Micros,
+}
+
+impl Default for FireworksShow {
+  fn default() -> Self {
+    Self {
+      rockets: Particles::new(Edges::Vanish),
+      sparks: Particles::new(Edges::Vanish),
+      rng: None,
+      next_launch: 0,
+    }
+  }
+}
+
+impl Show for FireworksShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let n = frame.len();
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+    let gravity = -nl!(n);
+
+    if self.next_launch <= ctx.delta {
+      self.next_launch = rng.gen_range(500_000..2_000_000);
+      // rising with `v` reaches a height of `v^2 / 2g`, so this bursts between half and 90%
+      let height = nl!(rng.gen_range(0.5f32..0.9));
+      let rocket = Particle::new(ZERO, NormRgbw::from_kelvin(2200).scale(nl!(0.5)))
+        .with_velocity(nl!(n) * cordic::sqrt(2 * height))
+        .with_gravity(gravity);
+      self.rockets.spawn(rocket);
+    } else {
+      self.next_launch -= ctx.delta;
+    }
+
+    self.rockets.step(ctx.delta, n);
+    let bursts: Vec<Fix32> = self
+      .rockets
+      .iter()
+      .filter(|rocket| rocket.vel <= ZERO)
+      .map(|rocket| rocket.pos)
+      .collect();
+    self.rockets.retain(|rocket| rocket.vel > ZERO);
+    for pos in bursts {
+      let hue = nl!(rng.gen::<f32>());
+      for _ in 0..rng.gen_range(15..30) {
+        let color = NormHsv::new(
+          (hue + nl!(rng.gen_range(-0.05f32..0.05))).rem_euclid(ONE),
+          nl!(rng.gen_range(0.6f32..1.0)),
+          ONE,
+        )
+        .into();
+        let spark = Particle::new(pos, color)
+          .with_velocity(nl!(n) * nl!(rng.gen_range(-0.4f32..0.4)))
+          .with_gravity(gravity / 4)
+          .with_damping(nl!(1.5))
+          .with_lifetime(nl!(rng.gen_range(0.8f32..2.0)));
+        self.sparks.spawn(spark);
+      }
+    }
+    self.sparks.step(ctx.delta, n);
+
+    frame.fill(NormRgbw::NONE);
+    self.sparks.render(frame);
+    self.rockets.render(frame);
+    FRAME_TIME
+  }
+}
+
+/// Comets circling the strip, leaving tails which fade out behind them.
+pub struct CometShow {
+  count: usize,
+  /// Seconds until the tail has faded to about a third.
+  tail: Fix32,
+  comets: Particles,
+  rng: Option<SmallRng>,
+  /// Length of the strip the comets were spawned on.
+  len: usize,
+}
+
+impl Default for CometShow {
+  fn default() -> Self {
+    Self::new(3, nl!(0.5))
+  }
+}
+
+impl CometShow {
+  pub fn new(count: usize, tail: Fix32) -> Self {
+    Self {
+      count: count.clamp(1, 20),
+      tail: tail.clamp(nl!(0.05), nl!(10)),
+      comets: Particles::new(Edges::Wrap),
+      rng: None,
+      len: 0,
+    }
+  }
+}
+
+impl Show for CometShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let n = frame.len();
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+
+    if self.len != n {
+      self.len = n;
+      self.comets.clear();
+      frame.fill(NormRgbw::NONE);
+      for _ in 0..self.count {
+        let color = NormHsv::new(nl!(rng.gen::<f32>()), nl!(0.8), ONE).into();
+        // between a tenth and half of the strip per second, in either direction
+        let mut vel = nl!(n) * nl!(rng.gen_range(0.1f32..0.5));
+        if rng.gen() {
+          vel = -vel;
+        }
+        let comet = Particle::new(nl!(rng.gen_range(0..n.max(1))), color).with_velocity(vel);
+        self.comets.spawn(comet);
+      }
+    }
+
+    // the previous frame fades out, which leaves the tails
+    let dt = Fix32::from_bits((ctx.delta.min(100_000) * (1 << 16) / 1_000_000) as i32);
+    let keep = (ONE - dt / self.tail).max(ZERO);
+    for led in frame.iter_mut() {
+      *led = led.scale(keep);
+    }
+
+    // fast comets are drawn at every light they pass, so the tails don't have gaps
+    let fastest = self
+      .comets
+      .iter()
+      .map(|c| c.vel.abs())
+      .max()
+      .unwrap_or(ZERO);
+    let steps = (fastest * dt).ceil().to_num::<u64>().max(1);
+    for _ in 0..steps {
+      self.comets.step(ctx.delta / steps, n);
+      self.comets.render(frame);
+    }
+    FRAME_TIME
+  }
+}
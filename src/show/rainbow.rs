@@ -0,0 +1,41 @@
+use crate::{
+  color::{NormHsv, NormRgbw},
+  nl, Fix32, ONE,
+};
+
+use super::{FrameContext, Micros, Show, FRAME_TIME};
+
+/// Time for the rainbow to move once along the strip at speed 1.
+const PERIOD: Micros = 10_000_000;
+
+/// All hues along the strip, moving over time.
+pub struct RainbowShow {
+  /// Negative speeds move the rainbow backwards.
+  speed: Fix32,
+}
+
+impl Default for RainbowShow {
+  fn default() -> Self {
+    Self::new(ONE)
+  }
+}
+
+impl RainbowShow {
+  pub fn new(speed: Fix32) -> Self {
+    Self { speed }
+  }
+}
+
+impl Show for RainbowShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    // in turns, computed from the total time so it doesn't jump for fractional speeds
+    let turns = ctx.time as i128 * self.speed.to_bits() as i128 / PERIOD as i128;
+    let shift = Fix32::from_bits(turns.rem_euclid(1 << 16) as i32);
+    let n = frame.len();
+    for (l, led) in frame.iter_mut().enumerate() {
+      let hue = (nl!(l) / nl!(n.max(1)) - shift).rem_euclid(ONE);
+      *led = NormHsv::new(hue, ONE, ONE).into();
+    }
+    FRAME_TIME
+  }
+}
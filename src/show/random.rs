@@ -1,32 +1,22 @@
-use rand::{Rng, SeedableRng};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
 
-use crate::{
-  app::monotonics,
-  light::{color::NormHsv, controller::MemoryController, Lights},
-  return_cancel,
-};
+use crate::color::{NormHsv, NormRgbw};
 
-use super::Show;
+use super::{FrameContext, Micros, Show, FRAME_TIME};
 
 #[derive(Default)]
-pub struct RandomShow;
+pub struct RandomShow {
+  rng: Option<SmallRng>,
+}
 
 impl Show for RandomShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut crate::light::controller::ColorMemoryController,
-    _asm_delay: crate::util::AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    let mut rng = rand::rngs::SmallRng::seed_from_u64(monotonics::now().ticks());
-    loop {
-      for l in 0..Lights::N {
-        ctrl.set(l, rng.gen::<NormHsv>().into());
-      }
-      ctrl.display(config);
-      return_cancel!(cancel);
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+    for led in frame.iter_mut() {
+      *led = rng.gen::<NormHsv>().into();
     }
+    FRAME_TIME
   }
 }
@@ -1,67 +1,378 @@
-use alloc::vec::Vec;
-use rand::{prelude::Distribution, Rng, SeedableRng};
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
 
 use crate::{
-  app::monotonics,
-  light::{
-    color::{NormHsv, NormRgbw},
-    controller::{ColorMemoryController, MemoryController, MemoryControllerExt},
-    Lights,
-  },
-  return_cancel,
-  util::AsmDelay,
+  color::{NormHsv, NormRgbw},
+  geometry::Geometry,
 };
 
-use super::Show;
+use super::{Action, FrameContext, Memory, Micros, Show, FRAME_TIME};
+
+/// Time of a step of a snake without fruits.
+const START_STEP: Micros = 300_000;
+/// Every fruit makes the snake this much faster.
+const STEP_DECREASE: Micros = 10_000;
+const MIN_STEP: Micros = 60_000;
+/// More turns aren't queued until the next step.
+const MAX_TURNS: usize = 3;
+/// Time the snake flashes after the game ended.
+const FLASH_TIME: Micros = 1_000_000;
+const FLASH_PERIOD: Micros = 200_000;
+/// Time it takes to count up a single point of the score.
+const COUNT_TIME: Micros = 100_000;
+/// Time the score stays visible before the next game starts.
+const SCORE_TIME: Micros = 3_000_000;
+
+/// Key of the high score in the memory.
+const HIGH_SCORE: &str = "snake";
+
+/// The cells the snake moves on, wrapping around at the edges.
+struct Board {
+  width: usize,
+  height: usize,
+  /// The light of every cell, row by row.
+  lights: Vec<Option<usize>>,
+}
+
+impl Board {
+  /// A matrix if the lights form a grid, otherwise the strip in the order of the lights.
+  fn new(geometry: &Geometry, n: usize) -> Self {
+    Self::grid(geometry, n).unwrap_or_else(|| Self {
+      width: n,
+      height: 1,
+      lights: (0..n).map(Some).collect(),
+    })
+  }
+
+  fn grid(geometry: &Geometry, n: usize) -> Option<Self> {
+    let points = geometry.points();
+    if points.len() != n {
+      return None;
+    }
+    let mut xs: Vec<_> = points.iter().map(|p| p.x).collect();
+    let mut ys: Vec<_> = points.iter().map(|p| p.y).collect();
+    for values in [&mut xs, &mut ys] {
+      values.sort_unstable();
+      values.dedup();
+    }
+    let (width, height) = (xs.len(), ys.len());
+    if width < 2 || height < 2 || width * height != n {
+      return None;
+    }
+    let mut lights = vec![None; n];
+    for (i, p) in points.iter().enumerate() {
+      let x = xs.binary_search(&p.x).ok()?;
+      let y = ys.binary_search(&p.y).ok()?;
+      let light = &mut lights[y * width + x];
+      if light.is_some() {
+        return None;
+      }
+      *light = Some(i);
+    }
+    Some(Self {
+      width,
+      height,
+      lights,
+    })
+  }
+
+  fn len(&self) -> usize {
+    self.width * self.height
+  }
+
+  /// The cell next to `cell` in the direction `(dx, dy)`.
+  fn neighbor(&self, cell: usize, (dx, dy): (i32, i32)) -> usize {
+    let x = (cell % self.width) as i32 + dx;
+    let y = (cell / self.width) as i32 + dy;
+    let x = x.rem_euclid(self.width as i32) as usize;
+    let y = y.rem_euclid(self.height as i32) as usize;
+    y * self.width + x
+  }
+
+  fn set(&self, frame: &mut [NormRgbw], cell: usize, color: NormRgbw) {
+    if let Some(led) = self.lights[cell].and_then(|i| frame.get_mut(i)) {
+      *led = color;
+    }
+  }
+}
 
-struct Snake {
-  pub pos: usize,
-  pub tail: Vec<NormRgbw>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+  Left,
+  Right,
 }
 
 struct Fruit {
-  pub pos: usize,
-  pub color: NormRgbw,
+  cell: usize,
+  color: NormRgbw,
 }
 
-#[derive(Default)]
-pub struct SnakeShow;
-impl Show for SnakeShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut ColorMemoryController,
-    _asm_delay: AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    let mut rng = rand::rngs::SmallRng::seed_from_u64(monotonics::now().ticks());
-    let pos_distr = rand::distributions::Uniform::new(0, Lights::N);
-    let mut snake = Snake {
-      pos: pos_distr.sample(&mut rng),
-      tail: Vec::new(),
+/// Outcome of a step of the snake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+  Moved,
+  /// The snake ran into itself.
+  Crashed,
+  /// The snake covers the whole board.
+  Won,
+}
+
+enum Phase {
+  Playing,
+  /// The game ended `elapsed` ago with `score`.
+  Over {
+    won: bool,
+    score: u32,
+    /// The high score before the game.
+    high: u32,
+    elapsed: Micros,
+  },
+}
+
+struct Game {
+  board: Board,
+  /// The cells of the snake, starting with the head.
+  body: VecDeque<usize>,
+  /// The color of every segment, the snake takes on the colors of the fruits it ate.
+  colors: VecDeque<NormRgbw>,
+  /// Step along the x and y axis of the board.
+  direction: (i32, i32),
+  turns: VecDeque<Turn>,
+  fruit: Option<Fruit>,
+  /// Time since the last step.
+  pending: Micros,
+  phase: Phase,
+}
+
+impl Game {
+  fn new(board: Board, rng: &mut SmallRng) -> Self {
+    let head = rng.gen_range(0..board.len());
+    let mut game = Self {
+      board,
+      body: VecDeque::from([head]),
+      colors: VecDeque::from([NormRgbw::WHITE]),
+      direction: (1, 0),
+      turns: VecDeque::new(),
+      fruit: None,
+      pending: 0,
+      phase: Phase::Playing,
     };
-    let mut fruit = Fruit {
-      pos: pos_distr.sample(&mut rng),
+    game.fruit = game.spawn_fruit(rng);
+    game
+  }
+
+  fn score(&self) -> u32 {
+    self.body.len() as u32 - 1
+  }
+
+  /// The snake gets faster the longer it is.
+  fn step_time(&self) -> Micros {
+    START_STEP
+      .saturating_sub(self.score() as Micros * STEP_DECREASE)
+      .max(MIN_STEP)
+  }
+
+  /// A fruit in a random cell which isn't covered by the snake, `None` if there is none left.
+  fn spawn_fruit(&self, rng: &mut SmallRng) -> Option<Fruit> {
+    let free = self.board.len() - self.body.len();
+    if free == 0 {
+      return None;
+    }
+    let nth = rng.gen_range(0..free);
+    let cell = (0..self.board.len())
+      .filter(|cell| !self.body.contains(cell))
+      .nth(nth)?;
+    Some(Fruit {
+      cell,
       color: rng.gen::<NormHsv>().into(),
+    })
+  }
+
+  fn turn(&mut self, turn: Turn) {
+    let (dx, dy) = self.direction;
+    if self.board.height == 1 {
+      // there is no way to the side on a strip, so the snake turns around,
+      // towards the start for left and the end for right
+      let towards = match turn {
+        Turn::Left => -1,
+        Turn::Right => 1,
+      };
+      if dx != towards {
+        self.body.make_contiguous().reverse();
+        self.colors.make_contiguous().reverse();
+        self.direction = (towards, 0);
+      }
+    } else {
+      // the y axis points down
+      self.direction = match turn {
+        Turn::Left => (dy, -dx),
+        Turn::Right => (-dy, dx),
+      };
+    }
+  }
+
+  /// Moves the snake by a single cell.
+  fn step(&mut self, rng: &mut SmallRng) -> Step {
+    if let Some(turn) = self.turns.pop_front() {
+      self.turn(turn);
+    }
+    let head = self.board.neighbor(self.body[0], self.direction);
+    let eats = match &self.fruit {
+      Some(fruit) => fruit.cell == head,
+      None => false,
     };
+    if !eats {
+      // the tail moves out of the way
+      self.body.pop_back();
+    }
+    if self.body.contains(&head) {
+      return Step::Crashed;
+    }
+    self.body.push_front(head);
+    if eats {
+      let color = self.fruit.take().map(|fruit| fruit.color);
+      self.colors.push_front(color.unwrap_or(NormRgbw::WHITE));
+      self.fruit = self.spawn_fruit(rng);
+      if self.fruit.is_none() {
+        return Step::Won;
+      }
+    }
+    Step::Moved
+  }
+
+  fn handle_action(&mut self, action: Action) {
+    let turn = match action {
+      Action::Prev => Turn::Left,
+      Action::Next => Turn::Right,
+      _ => return,
+    };
+    if self.turns.len() < MAX_TURNS {
+      self.turns.push_back(turn);
+    }
+  }
+
+  /// Advances the game by `delta`, returns whether a new game should start.
+  /// A new high score is kept in `memory`.
+  fn update(&mut self, delta: Micros, rng: &mut SmallRng, memory: &Memory) -> bool {
+    match &mut self.phase {
+      Phase::Playing => {
+        self.pending += delta;
+        while self.pending >= self.step_time() {
+          self.pending -= self.step_time();
+          let step = self.step(rng);
+          if step != Step::Moved {
+            let score = self.score();
+            let high = memory.get(HIGH_SCORE).unwrap_or(0);
+            if score > high {
+              memory.set(HIGH_SCORE, score);
+            }
+            self.phase = Phase::Over {
+              won: step == Step::Won,
+              score,
+              high,
+              elapsed: 0,
+            };
+            break;
+          }
+        }
+        false
+      }
+      Phase::Over { score, elapsed, .. } => {
+        *elapsed += delta;
+        *elapsed >= FLASH_TIME + *score as Micros * COUNT_TIME + SCORE_TIME
+      }
+    }
+  }
 
-    loop {
-      ctrl.set_all(NormRgbw::NONE);
-      if snake.pos == fruit.pos {
-        snake.tail.insert(0, fruit.color);
-        // TODO: don't spawn fruits inside snake
-        fruit.pos = pos_distr.sample(&mut rng);
-        fruit.color = rng.gen::<NormHsv>().into();
+  fn draw(&self, frame: &mut [NormRgbw]) {
+    frame.fill(NormRgbw::NONE);
+    match self.phase {
+      Phase::Playing => self.draw_board(frame),
+      Phase::Over { won, elapsed, .. } if elapsed < FLASH_TIME => {
+        // a full board flashes in the colors of the snake, a crash in red
+        if matches!(elapsed / FLASH_PERIOD % 2, 0) {
+          if won {
+            self.draw_board(frame);
+          } else {
+            for &cell in &self.body {
+              self.board.set(frame, cell, NormRgbw::RED);
+            }
+          }
+        }
       }
-      for (i, &segment) in snake.tail.iter().enumerate() {
-        let pos = (snake.pos + i) % Lights::N;
-        ctrl.set(pos, segment);
+      Phase::Over {
+        score,
+        high,
+        elapsed,
+        ..
+      } => {
+        // the score counts up along the strip, the high score is marked unless it was beaten
+        let counted = ((elapsed - FLASH_TIME) / COUNT_TIME).min(score as Micros) as usize;
+        let record = score > high;
+        if !record && high > 0 {
+          if let Some(led) = frame.get_mut(high as usize - 1) {
+            *led = NormRgbw::WHITE;
+          }
+        }
+        let color = if record {
+          NormRgbw::YELLOW
+        } else {
+          NormRgbw::GREEN
+        };
+        let lit = counted.min(frame.len());
+        frame[..lit].fill(color);
       }
-      ctrl.set(fruit.pos, fruit.color);
-      ctrl.display(config);
-      snake.pos = (snake.pos + Lights::N - 1) % Lights::N;
-      return_cancel!(cancel);
     }
   }
+
+  fn draw_board(&self, frame: &mut [NormRgbw]) {
+    for (&cell, &color) in self.body.iter().zip(&self.colors) {
+      self.board.set(frame, cell, color);
+    }
+    if let Some(fruit) = &self.fruit {
+      self.board.set(frame, fruit.cell, fruit.color);
+    }
+  }
+}
+
+/// Snake controlled by the remote, Prev and Next turn left and right.
+/// It's played on a matrix if the geometry is one, otherwise along the strip,
+/// where Prev and Next make the snake head towards the start and the end.
+/// The snake gets faster with every fruit and the game ends when it runs into itself,
+/// or is won when the snake fills the whole board.
+#[derive(Default)]
+pub struct SnakeShow {
+  rng: Option<SmallRng>,
+  game: Option<Game>,
+  /// Number of lights the game was started with.
+  len: usize,
+}
+
+impl Show for SnakeShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let n = frame.len();
+    if n == 0 {
+      return FRAME_TIME;
+    }
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+    if self.len != n {
+      self.len = n;
+      self.game = None;
+    }
+    let game = self
+      .game
+      .get_or_insert_with(|| Game::new(Board::new(ctx.geometry, n), rng));
+
+    if let Some(action) = ctx.action {
+      game.handle_action(action);
+    }
+    if game.update(ctx.delta, rng, ctx.memory) {
+      *game = Game::new(Board::new(ctx.geometry, n), rng);
+    }
+    game.draw(frame);
+    FRAME_TIME
+  }
 }
@@ -1,28 +1,25 @@
 use crate::{
-  light::{
-    color::{NormHsv, NormRgbw},
-    controller::MemoryController,
-    Lights,
-  },
-  return_cancel,
+  color::{NormHsv, NormRgbw},
+  nl, ONE, ZERO,
 };
-use arclib::{nl, ONE, ZERO};
-use rtic::Mutex;
 
-use super::Show;
+use super::{Action, FrameContext, Micros, Show, FRAME_TIME};
 
 pub struct SpotlightShow {
   light_hsv: NormHsv,
   light_pos: usize,
   light_ext: usize,
+  /// Number of lights `light_pos` and `light_ext` refer to.
+  n: usize,
   input_state: InputState,
 }
 impl Default for SpotlightShow {
   fn default() -> Self {
     Self {
       light_hsv: NormRgbw::RED.into(),
-      light_pos: Lights::N / 2,
-      light_ext: Lights::N / 2,
+      light_pos: 0,
+      light_ext: 0,
+      n: 0,
       input_state: InputState::default(),
     }
   }
@@ -39,6 +36,8 @@ impl SpotlightShow {
 #[derive(Default)]
 struct InputState {
   controllable: Controllable,
+  /// Number of consecutive Prev/Next presses, used for acceleration.
+  repeats: i32,
 }
 
 #[derive(Default)]
@@ -51,102 +50,86 @@ enum Controllable {
   Ext,
 }
 
-impl Show for SpotlightShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut crate::light::controller::ColorMemoryController,
-    _asm_delay: crate::util::AsmDelay,
-    remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    // TODO: remove busy loop
-    let mut time = 0;
-    loop {
-      // TODO: Can we avoid this lock with a channel? And is it good to do so?
-      remote_input.lock(|input| match input.0.take() {
-        Some(action) => {
-          use crate::input::remote::Action;
-
-          let (delta_float, delta_int) = match action {
-            Action::Prev => {
-              time += 1;
-              (
-                nl!(-0.01) + time * nl!(-0.0001),
-                (nl!(-1) + time * nl!(-0.01)).ceil().to_num::<isize>(),
-              )
-            }
-            Action::Next => {
-              time += 1;
-              (
-                nl!(0.01) + time * nl!(0.0001),
-                (nl!(1) + time * nl!(0.01)).ceil().to_num::<isize>(),
-              )
-            }
-            _ => {
-              time = 0;
-              (ZERO, 0)
-            }
-          };
-
-          let controllable = &mut self.input_state.controllable;
-          match action {
-            Action::One => *controllable = Controllable::Hue,
-            Action::Two => *controllable = Controllable::Sat,
-            Action::Three => *controllable = Controllable::Val,
-            Action::Four => *controllable = Controllable::Pos,
-            Action::Five => *controllable = Controllable::Ext,
-            _ => {}
-          }
+impl SpotlightShow {
+  fn handle_action(&mut self, action: Action) {
+    let n = self.n;
+    let repeats = &mut self.input_state.repeats;
+    let (delta_float, delta_int) = match action {
+      Action::Prev => {
+        *repeats += 1;
+        (
+          nl!(-0.01) + *repeats * nl!(-0.0001),
+          (nl!(-1) + *repeats * nl!(-0.01)).ceil().to_num::<isize>(),
+        )
+      }
+      Action::Next => {
+        *repeats += 1;
+        (
+          nl!(0.01) + *repeats * nl!(0.0001),
+          (nl!(1) + *repeats * nl!(0.01)).ceil().to_num::<isize>(),
+        )
+      }
+      _ => {
+        *repeats = 0;
+        (ZERO, 0)
+      }
+    };
 
-          match self.input_state.controllable {
-            Controllable::Hue => {
-              self.light_hsv.hue = (self.light_hsv.hue + delta_float).rem_euclid(ONE);
-            }
-            Controllable::Sat => {
-              self.light_hsv.sat = ONE.min(ZERO.max(self.light_hsv.sat + delta_float))
-            }
-            Controllable::Val => {
-              self.light_hsv.val = ONE.min(ZERO.max(self.light_hsv.val + delta_float))
-            }
-            _ => {}
-          };
+    let controllable = &mut self.input_state.controllable;
+    match action {
+      Action::One => *controllable = Controllable::Hue,
+      Action::Two => *controllable = Controllable::Sat,
+      Action::Three => *controllable = Controllable::Val,
+      Action::Four => *controllable = Controllable::Pos,
+      Action::Five => *controllable = Controllable::Ext,
+      _ => {}
+    }
 
-          match self.input_state.controllable {
-            Controllable::Pos => {
-              self.light_pos = Lights::N.min(0.max(self.light_pos as isize + delta_int) as usize);
+    match self.input_state.controllable {
+      Controllable::Hue => {
+        self.light_hsv.hue = (self.light_hsv.hue + delta_float).rem_euclid(ONE);
+      }
+      Controllable::Sat => self.light_hsv.sat = ONE.min(ZERO.max(self.light_hsv.sat + delta_float)),
+      Controllable::Val => self.light_hsv.val = ONE.min(ZERO.max(self.light_hsv.val + delta_float)),
+      _ => {}
+    };
 
-              let boundary_dist = self.light_pos.min(Lights::N - self.light_pos);
-              self.light_ext = self.light_ext.min(boundary_dist);
-            }
-            Controllable::Ext => {
-              self.light_ext =
-                (Lights::N / 2).min(0.max(self.light_ext as isize + delta_int) as usize);
-              if self.light_pos < Lights::N - self.light_pos {
-                self.light_pos = self.light_pos.max(self.light_ext);
-              } else {
-                self.light_pos = self.light_pos.min(Lights::N - self.light_ext);
-              }
-            }
-            _ => {}
-          }
-        }
-        None => {}
-      });
+    match self.input_state.controllable {
+      Controllable::Pos => {
+        self.light_pos = n.min(0.max(self.light_pos as isize + delta_int) as usize);
 
-      let from = self.light_pos - self.light_ext;
-      let to = self.light_pos + self.light_ext;
-      for l in 0..from {
-        ctrl.set(l, NormRgbw::NONE);
+        let boundary_dist = self.light_pos.min(n - self.light_pos);
+        self.light_ext = self.light_ext.min(boundary_dist);
       }
-      for l in from..to {
-        ctrl.set(l, self.light_hsv.into());
-      }
-      for l in to..Lights::N {
-        ctrl.set(l, NormRgbw::NONE);
+      Controllable::Ext => {
+        self.light_ext = (n / 2).min(0.max(self.light_ext as isize + delta_int) as usize);
+        if self.light_pos < n - self.light_pos {
+          self.light_pos = self.light_pos.max(self.light_ext);
+        } else {
+          self.light_pos = self.light_pos.min(n - self.light_ext);
+        }
       }
-      ctrl.display(config);
-      return_cancel!(cancel);
+      _ => {}
     }
   }
 }
+
+impl Show for SpotlightShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if self.n != frame.len() {
+      self.n = frame.len();
+      self.light_pos = self.n / 2;
+      self.light_ext = self.n / 2;
+    }
+    if let Some(action) = ctx.action {
+      self.handle_action(action);
+    }
+
+    let from = self.light_pos - self.light_ext;
+    let to = self.light_pos + self.light_ext;
+    frame[..from].fill(NormRgbw::NONE);
+    frame[from..to].fill(self.light_hsv.into());
+    frame[to..].fill(NormRgbw::NONE);
+    FRAME_TIME
+  }
+}
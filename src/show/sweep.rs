@@ -0,0 +1,58 @@
+use crate::{
+  color::{NormHsv, NormRgbw},
+  nl, Fix32, ONE,
+};
+
+use super::{FrameContext, Micros, Show, FRAME_TIME};
+
+/// A plane of light sweeping back and forth along the x axis of the geometry,
+/// changing its color with every pass.
+pub struct SweepShow {
+  /// Time of a single pass.
+  period: Micros,
+  /// Half of the thickness of the plane, in units of the geometry.
+  width: Fix32,
+}
+
+impl Default for SweepShow {
+  fn default() -> Self {
+    Self {
+      period: 3_000_000,
+      width: nl!(0.1),
+    }
+  }
+}
+
+impl Show for SweepShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    let points = ctx.geometry.points();
+    let (min, max) = points
+      .iter()
+      .fold((Fix32::MAX, Fix32::MIN), |(min, max), p| {
+        (min.min(p.x), max.max(p.x))
+      });
+    if min > max {
+      return FRAME_TIME;
+    }
+
+    let pass = ctx.time / self.period;
+    let t = Fix32::from_bits((ctx.time % self.period * (1 << 16) / self.period) as i32);
+    // back and forth
+    let t = match pass % 2 {
+      0 => t,
+      _ => ONE - t,
+    };
+    let x = min + t * (max - min);
+    let color: NormRgbw = NormHsv::new(nl!(pass % 7) / nl!(7), ONE, ONE).into();
+
+    ctx.geometry.sample(frame, |point| {
+      let distance = (point.x - x).abs();
+      if distance < self.width {
+        color.scale(ONE - distance / self.width)
+      } else {
+        NormRgbw::NONE
+      }
+    });
+    FRAME_TIME
+  }
+}
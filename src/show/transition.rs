@@ -0,0 +1,203 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{color::NormRgbw, nl, Fix32};
+
+use super::{Action, FrameContext, Micros, Show, FRAME_TIME};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionStyle {
+  /// Blends all lights at once.
+  Crossfade,
+  /// The incoming show moves in from the start of the strip.
+  Wipe,
+  /// The lights switch to the incoming show one by one, in random order.
+  Dissolve,
+}
+
+/// Longest transition, longer ones would overflow the progress.
+const MAX_DURATION: Micros = 3_600_000_000;
+
+/// How the driver switches from one show to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+  pub style: TransitionStyle,
+  /// Zero switches immediately.
+  pub duration: Micros,
+}
+
+impl Default for Transition {
+  fn default() -> Self {
+    Self {
+      style: TransitionStyle::Crossfade,
+      duration: 1_000_000,
+    }
+  }
+}
+
+impl Transition {
+  /// Wraps the incoming show, so it starts with the transition.
+  pub fn apply(self, show: Box<dyn Show + Send>) -> Box<dyn Show + Send> {
+    if self.duration == 0 {
+      show
+    } else {
+      Box::new(TransitionShow::new(show, self))
+    }
+  }
+
+  /// Changes the transition, given as `off` or `<crossfade|wipe|dissolve> [ms]`.
+  pub fn update(&mut self, setting: &str) -> Result<(), &'static str> {
+    let mut words = setting.split_whitespace();
+    let style = match words.next() {
+      Some("off") => {
+        self.duration = 0;
+        return Ok(());
+      }
+      Some("crossfade") => TransitionStyle::Crossfade,
+      Some("wipe") => TransitionStyle::Wipe,
+      Some("dissolve") => TransitionStyle::Dissolve,
+      _ => return Err("expected off, crossfade, wipe or dissolve"),
+    };
+    let duration = match (words.next(), words.next()) {
+      (None, None) if self.duration != 0 => self.duration,
+      (None, None) => Transition::default().duration,
+      (Some(ms), None) => {
+        let ms: Micros = ms.parse().map_err(|_| "invalid duration")?;
+        ms.checked_mul(1000)
+          .filter(|&duration| duration <= MAX_DURATION)
+          .ok_or("duration too long")?
+      }
+      _ => return Err("expected `<style> [ms]`"),
+    };
+    *self = Transition { style, duration };
+    Ok(())
+  }
+}
+
+impl fmt::Display for Transition {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.duration == 0 {
+      return write!(f, "off");
+    }
+    let style = match self.style {
+      TransitionStyle::Crossfade => "crossfade",
+      TransitionStyle::Wipe => "wipe",
+      TransitionStyle::Dissolve => "dissolve",
+    };
+    write!(f, "{} {}ms", style, self.duration / 1000)
+  }
+}
+
+/// Blends from the previous frame into the incoming show.
+/// After the transition it only forwards to the incoming show.
+pub struct TransitionShow {
+  show: Box<dyn Show + Send>,
+  transition: Transition,
+  state: State,
+  /// Time at which the incoming show wants to render its next frame.
+  next_frame: Micros,
+  last_frame: Micros,
+  /// Action for the incoming show, kept until it renders.
+  action: Option<Action>,
+}
+
+enum State {
+  Starting,
+  Blending {
+    /// Last frame of the outgoing show.
+    from: Vec<NormRgbw>,
+    /// Frame of the incoming show.
+    to: Vec<NormRgbw>,
+    /// Progress at which the lights switch, for `Dissolve`.
+    thresholds: Vec<Fix32>,
+  },
+  Done,
+}
+
+impl TransitionShow {
+  pub fn new(show: Box<dyn Show + Send>, transition: Transition) -> Self {
+    Self {
+      show,
+      transition,
+      state: State::Starting,
+      next_frame: 0,
+      last_frame: 0,
+      action: None,
+    }
+  }
+}
+
+impl Show for TransitionShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let State::Starting = self.state {
+      let thresholds = match self.transition.style {
+        TransitionStyle::Dissolve => {
+          let mut rng = SmallRng::seed_from_u64(ctx.seed);
+          frame.iter().map(|_| nl!(rng.gen::<f32>())).collect()
+        }
+        _ => Vec::new(),
+      };
+      self.state = State::Blending {
+        from: frame.to_vec(),
+        // the incoming show starts with the previous frame, like without transition
+        to: frame.to_vec(),
+        thresholds,
+      };
+    }
+    if ctx.action.is_some() {
+      self.action = ctx.action;
+    }
+
+    if ctx.time >= self.next_frame {
+      let show_ctx = FrameContext {
+        delta: ctx.time - self.last_frame,
+        action: self.action.take(),
+        ..*ctx
+      };
+      self.last_frame = ctx.time;
+      let target = match &mut self.state {
+        State::Blending { to, .. } => to.as_mut_slice(),
+        _ => &mut *frame,
+      };
+      self.next_frame = ctx.time + self.show.render(&show_ctx, target);
+    }
+    let until_next_frame = self.next_frame - ctx.time;
+
+    let (from, to, thresholds) = match &self.state {
+      State::Blending {
+        from,
+        to,
+        thresholds,
+      } => (from, to, thresholds),
+      _ => return until_next_frame,
+    };
+
+    if ctx.time >= self.transition.duration {
+      frame.copy_from_slice(to);
+      self.state = State::Done;
+      return until_next_frame;
+    }
+
+    let t = Fix32::from_bits((ctx.time * (1 << 16) / self.transition.duration) as i32);
+    match self.transition.style {
+      TransitionStyle::Crossfade => {
+        for ((led, from), to) in frame.iter_mut().zip(from).zip(to) {
+          *led = from.gradient(*to, t);
+        }
+      }
+      TransitionStyle::Wipe => {
+        let edge = (t * nl!(frame.len())).to_num::<usize>();
+        frame[..edge].copy_from_slice(&to[..edge]);
+        frame[edge..].copy_from_slice(&from[edge..]);
+      }
+      TransitionStyle::Dissolve => {
+        for (i, led) in frame.iter_mut().enumerate() {
+          *led = if thresholds[i] < t { to[i] } else { from[i] };
+        }
+      }
+    }
+    until_next_frame.min(FRAME_TIME)
+  }
+}
@@ -0,0 +1,181 @@
+use alloc::{vec, vec::Vec};
+use core::str::FromStr;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+  color::{NormHsv, NormRgbw},
+  nl, Fix32, ONE, ZERO,
+};
+
+use super::{adjust, Action, FrameContext, Micros, Show, Variants, FRAME_TIME};
+
+/// Longer frames are treated as this long, so a stalled show doesn't go dark at once.
+const MAX_DELTA: Micros = 100_000;
+/// The fade speed of every star is varied by up to this much, relative to `fade`.
+const FADE_VARIATION: f32 = 0.5;
+
+/// Colors the stars are randomly picked from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TwinklePalette {
+  /// The white channel.
+  #[default]
+  White,
+  /// Warm white between candle light and halogen.
+  Warm,
+  /// Any saturated hue.
+  Rainbow,
+  /// Blue and cyan to white.
+  Ice,
+}
+
+impl Variants for TwinklePalette {
+  const ALL: &'static [Self] = &[Self::White, Self::Warm, Self::Rainbow, Self::Ice];
+}
+
+impl TwinklePalette {
+  fn color(self, rng: &mut SmallRng) -> NormRgbw {
+    match self {
+      TwinklePalette::White => NormRgbw::WHITE,
+      TwinklePalette::Warm => NormRgbw::from_kelvin(rng.gen_range(2000..3500)),
+      TwinklePalette::Rainbow => NormHsv::new(nl!(rng.gen::<f32>()), ONE, ONE).into(),
+      TwinklePalette::Ice => {
+        NormHsv::new(nl!(rng.gen_range(0.5f32..0.67)), nl!(rng.gen::<f32>()), ONE).into()
+      }
+    }
+  }
+}
+
+impl FromStr for TwinklePalette {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "white" => Ok(Self::White),
+      "warm" => Ok(Self::Warm),
+      "rainbow" => Ok(Self::Rainbow),
+      "ice" => Ok(Self::Ice),
+      _ => Err(()),
+    }
+  }
+}
+
+/// A light which ignited and is fading out, it's off once `brightness` reaches zero.
+#[derive(Debug, Clone, Copy)]
+struct Star {
+  color: NormRgbw,
+  brightness: Fix32,
+  /// Brightness lost per second.
+  fade: Fix32,
+}
+
+impl Star {
+  const OFF: Self = Self {
+    color: NormRgbw::NONE,
+    brightness: ZERO,
+    fade: ZERO,
+  };
+}
+
+/// Lights randomly ignite and fade out at their own speed in front of a background color.
+pub struct TwinkleShow {
+  /// Share of the lights which are lit on average, between 0 and 1.
+  density: Fix32,
+  /// Brightness lost per second on average, a star fades out in `1 / fade` seconds.
+  fade: Fix32,
+  background: NormRgbw,
+  palette: TwinklePalette,
+  stars: Vec<Star>,
+  rng: Option<SmallRng>,
+  controllable: Controllable,
+}
+
+impl Default for TwinkleShow {
+  fn default() -> Self {
+    Self::new(nl!(0.1), ONE, NormRgbw::NONE, TwinklePalette::default())
+  }
+}
+
+impl TwinkleShow {
+  pub fn new(density: Fix32, fade: Fix32, background: NormRgbw, palette: TwinklePalette) -> Self {
+    Self {
+      density: density.clamp(ZERO, ONE),
+      fade: fade.clamp(nl!(0.05), nl!(20)),
+      background,
+      palette,
+      stars: Vec::new(),
+      rng: None,
+      controllable: Controllable::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Controllable {
+  #[default]
+  Density,
+  Fade,
+  Palette,
+}
+
+impl Variants for Controllable {
+  const ALL: &'static [Self] = &[Self::Density, Self::Fade, Self::Palette];
+}
+
+impl TwinkleShow {
+  fn handle_action(&mut self, action: Action) {
+    let Some((controllable, up)) = adjust(&mut self.controllable, action) else {
+      return;
+    };
+    let step = |value: Fix32, min: Fix32, max: Fix32| {
+      let step = (value / 4).max(nl!(0.01));
+      if up {
+        (value + step).min(max)
+      } else {
+        (value - step).max(min)
+      }
+    };
+    match controllable {
+      Controllable::Density => self.density = step(self.density, ZERO, ONE),
+      Controllable::Fade => self.fade = step(self.fade, nl!(0.05), nl!(20)),
+      Controllable::Palette => self.palette = self.palette.next(),
+    }
+  }
+}
+
+impl Show for TwinkleShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let Some(action) = ctx.action {
+      self.handle_action(action);
+    }
+    if self.stars.len() != frame.len() {
+      self.stars = vec![Star::OFF; frame.len()];
+    }
+    let rng = self
+      .rng
+      .get_or_insert_with(|| SmallRng::seed_from_u64(ctx.seed));
+
+    // seconds since the previous frame
+    let dt = Fix32::from_bits((ctx.delta.min(MAX_DELTA) * (1 << 16) / 1_000_000) as i32);
+    // a star is lit for `1 / fade` seconds on average,
+    // so `density * fade` stars have to ignite per light and second
+    let chance = self.density * self.fade * dt;
+
+    for (led, star) in frame.iter_mut().zip(self.stars.iter_mut()) {
+      star.brightness = (star.brightness - star.fade * dt).max(ZERO);
+      if star.brightness == ZERO && nl!(rng.gen::<f32>()) < chance {
+        let variation = rng.gen_range(1.0 - FADE_VARIATION..1.0 + FADE_VARIATION);
+        *star = Star {
+          color: self.palette.color(rng),
+          brightness: ONE,
+          fade: self.fade * nl!(variation),
+        };
+      }
+      // squared, so the stars fade out smoothly instead of dropping off at the end
+      *led = self
+        .background
+        .gradient(star.color, star.brightness * star.brightness);
+    }
+    FRAME_TIME
+  }
+}
@@ -1,12 +1,6 @@
-use crate::{
-  light::{
-    color::NormRgbw,
-    controller::{MemoryController, MemoryControllerExt},
-  },
-  return_cancel,
-};
+use crate::color::NormRgbw;
 
-use super::Show;
+use super::{FrameContext, Micros, Show, FRAME_TIME};
 
 pub struct UniformShow(NormRgbw);
 impl UniformShow {
@@ -16,19 +10,8 @@ impl UniformShow {
 }
 
 impl Show for UniformShow {
-  fn run(
-    &mut self,
-    cancel: &mut crate::app::shared_resources::show_cancellation_token_lock,
-    ctrl: &mut crate::light::controller::ColorMemoryController,
-    _asm_delay: crate::util::AsmDelay,
-    _remote_input: &mut crate::app::shared_resources::remote_input_lock,
-    config: &mut crate::app::shared_resources::config_lock,
-  ) {
-    // TODO: remove busy loop
-    loop {
-      ctrl.set_all(self.0);
-      ctrl.display(config);
-      return_cancel!(cancel);
-    }
+  fn render(&mut self, _ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    frame.fill(self.0);
+    FRAME_TIME
   }
 }
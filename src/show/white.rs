@@ -0,0 +1,70 @@
+use crate::{color::NormRgbw, nl, Fix32, ONE, ZERO};
+
+use super::{adjust, Action, FrameContext, Micros, Show, Variants, FRAME_TIME};
+
+const MIN_KELVIN: u16 = 1000;
+const MAX_KELVIN: u16 = 10000;
+const KELVIN_STEP: u16 = 100;
+
+/// Uniform white light of an adjustable color temperature, e.g. for room lighting.
+/// `1` selects the temperature, `2` the brightness, which are changed with Prev/Next.
+pub struct WhiteShow {
+  kelvin: u16,
+  brightness: Fix32,
+  controllable: Controllable,
+}
+
+impl Default for WhiteShow {
+  fn default() -> Self {
+    Self::new(2700, ONE)
+  }
+}
+
+impl WhiteShow {
+  pub fn new(kelvin: u16, brightness: Fix32) -> Self {
+    Self {
+      kelvin: kelvin.clamp(MIN_KELVIN, MAX_KELVIN),
+      brightness: brightness.clamp(ZERO, ONE),
+      controllable: Controllable::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Controllable {
+  #[default]
+  Temperature,
+  Brightness,
+}
+
+impl Variants for Controllable {
+  const ALL: &'static [Self] = &[Self::Temperature, Self::Brightness];
+}
+
+impl WhiteShow {
+  fn handle_action(&mut self, action: Action) {
+    let Some((controllable, up)) = adjust(&mut self.controllable, action) else {
+      return;
+    };
+    let delta = if up { 1 } else { -1 };
+    match controllable {
+      Controllable::Temperature => {
+        let kelvin = self.kelvin as i32 + delta * KELVIN_STEP as i32;
+        self.kelvin = kelvin.clamp(MIN_KELVIN as i32, MAX_KELVIN as i32) as u16;
+      }
+      Controllable::Brightness => {
+        self.brightness = (self.brightness + delta * nl!(0.05)).clamp(ZERO, ONE);
+      }
+    }
+  }
+}
+
+impl Show for WhiteShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let Some(action) = ctx.action {
+      self.handle_action(action);
+    }
+    frame.fill(NormRgbw::from_kelvin(self.kelvin).scale(self.brightness));
+    FRAME_TIME
+  }
+}
@@ -0,0 +1,79 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{color::NormRgbw, segment::Segment};
+
+use super::{FrameContext, Micros, Nested, Show, FRAME_TIME};
+
+/// Splits a strip of the given length into segments.
+pub type Layout = fn(usize) -> Vec<Segment>;
+
+/// Runs a show in every segment at the same time.
+/// Every show gets a frame of the length of its segment and is rendered when it asks for it.
+/// Remote actions are forwarded to all of them.
+pub struct ZonesShow {
+  zones: Vec<Zone>,
+  /// Recomputes the segments whenever the length of the frame changes.
+  layout: Option<Layout>,
+  /// Length of the frame the segments were computed for.
+  len: usize,
+}
+
+struct Zone {
+  segment: Segment,
+  show: Nested,
+}
+
+impl ZonesShow {
+  pub fn new(zones: Vec<(Segment, Box<dyn Show + Send>)>) -> Self {
+    let zones = zones
+      .into_iter()
+      .map(|(segment, show)| Zone {
+        segment,
+        show: Nested::new(show),
+      })
+      .collect();
+    Self {
+      zones,
+      layout: None,
+      len: 0,
+    }
+  }
+
+  /// Runs the shows in the segments of `layout`, in order.
+  /// Unlike fixed segments they follow the length of the strip.
+  pub fn with_layout(layout: Layout, shows: Vec<Box<dyn Show + Send>>) -> Self {
+    let mut zones = Self::new(layout(0).into_iter().zip(shows).collect());
+    zones.layout = Some(layout);
+    zones
+  }
+
+  pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+    self.zones.iter().map(|zone| &zone.segment)
+  }
+}
+
+impl Show for ZonesShow {
+  fn render(&mut self, ctx: &FrameContext, frame: &mut [NormRgbw]) -> Micros {
+    if let (Some(layout), true) = (self.layout, self.len != frame.len()) {
+      self.len = frame.len();
+      for (zone, segment) in self.zones.iter_mut().zip(layout(frame.len())) {
+        zone.segment = segment;
+      }
+    }
+    for zone in self.zones.iter_mut().filter(|zone| zone.show.is_due(ctx)) {
+      let geometry = ctx.geometry.segment(&zone.segment);
+      let zone_ctx = FrameContext {
+        geometry: &geometry,
+        ..*ctx
+      };
+      zone.show.render(&zone_ctx, zone.segment.len);
+      zone.segment.write(frame, &zone.show.frame);
+    }
+    self
+      .zones
+      .iter()
+      .map(|zone| zone.show.until_next_frame(ctx.time))
+      .min()
+      .unwrap_or(FRAME_TIME)
+  }
+}
@@ -0,0 +1,69 @@
+//! Loading and saving the settings in flash, see `arclib::settings`.
+
+use arclib::{
+  settings::{Journal, Settings},
+  show::Micros,
+};
+use rtic::Mutex;
+
+use crate::{
+  app::{self, settings_task},
+  config::Config,
+  uprintln,
+  util::flash,
+};
+
+type Duration = <app::Monotonic as rtic::Monotonic>::Duration;
+
+/// Size of the flash region reserved for the settings in `memory.x`.
+const SETTINGS_SIZE: usize = 64 * 1024;
+/// The region is at the end of the 2MB flash.
+const SETTINGS_OFFSET: u32 = (2048 * 1024 - SETTINGS_SIZE) as u32;
+
+/// Settings are saved this long after a change, so a series of changes is saved at once.
+const SAVE_DELAY: Micros = 5_000_000;
+
+pub struct SettingsTask {
+  journal: Journal,
+  /// What's currently stored, to skip saves without changes.
+  saved: Settings,
+}
+
+impl SettingsTask {
+  /// Loads the settings from flash and applies them to `config`.
+  pub fn init(config: &mut Config) -> Self {
+    let (journal, settings) = Journal::load(flash::read(SETTINGS_OFFSET, SETTINGS_SIZE));
+    let saved = match settings {
+      Some(settings) => {
+        config.restore(&settings);
+        uprintln!("settings restored.");
+        settings
+      }
+      None => Settings::default(),
+    };
+    Self { journal, saved }
+  }
+}
+
+/// Saves the settings after a delay, unless a save is already pending.
+pub fn save_settings() {
+  let _ = settings_task::spawn_after(Duration::micros(SAVE_DELAY));
+}
+
+/// Writes the settings to flash if they changed since the last save.
+pub fn settings_task(ctx: settings_task::Context) {
+  let SettingsTask { journal, saved } = ctx.local.settings_task;
+  let mut config = ctx.shared.config;
+
+  let settings = config.lock(|config| config.settings());
+  if settings == *saved {
+    return;
+  }
+  let record = journal.append(&settings);
+  flash::write(
+    SETTINGS_OFFSET + record.offset as u32,
+    &record.page,
+    record.erase,
+  );
+  *saved = settings;
+}
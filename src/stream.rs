@@ -0,0 +1,294 @@
+//! Frames streamed from a computer, in the Adalight and TPM2 formats of ambient lighting software.
+//!
+//! Adalight frames start with `Ada`, the number of lights minus one as big endian `u16`
+//! and a checksum of it, followed by RGB for every light.
+//! TPM2 frames start with `0xc9`, the frame type and the size as big endian `u16`,
+//! followed by the data and `0x36`.
+
+use alloc::vec::Vec;
+
+use crate::show::Micros;
+
+const TPM2_START: u8 = 0xc9;
+const TPM2_DATA: u8 = 0xda;
+const TPM2_COMMAND: u8 = 0xc0;
+const TPM2_RESPONSE: u8 = 0xaa;
+const TPM2_END: u8 = 0x36;
+
+/// A frame is dropped when its next byte takes longer than this,
+/// so an interrupted frame doesn't swallow the input which follows.
+pub const FRAME_TIMEOUT: Micros = 100_000;
+
+pub enum Decoded<'a> {
+  /// The byte is part of a frame which isn't complete yet.
+  Pending,
+  /// A frame is complete, with RGB for every light.
+  Frame(&'a [u8]),
+  /// The bytes turned out not to be part of a frame, they should be handled otherwise.
+  Rejected(&'a [u8]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+  Idle,
+  AdalightHeader,
+  Tpm2Header,
+  /// Receiving `remaining` bytes of data.
+  Data {
+    remaining: usize,
+    tpm2: bool,
+  },
+  /// Command and response frames of TPM2 are skipped.
+  Skip {
+    remaining: usize,
+  },
+  Tpm2End {
+    data: bool,
+  },
+}
+
+/// Decodes frames byte by byte, so it can be fed from interrupts.
+pub struct StreamDecoder {
+  state: State,
+  header: [u8; 6],
+  header_len: usize,
+  data: Vec<u8>,
+  /// Frames with more data are rejected, enough for all lights.
+  max_len: usize,
+  /// When the last byte was received.
+  last_byte: Micros,
+}
+
+impl StreamDecoder {
+  /// Frames can have up to `nlights` lights.
+  pub fn new(nlights: usize) -> Self {
+    Self {
+      state: State::Idle,
+      header: [0; 6],
+      header_len: 0,
+      data: Vec::new(),
+      max_len: 3 * nlights,
+      last_byte: 0,
+    }
+  }
+
+  /// Decodes the next byte, received at `time`.
+  pub fn push(&mut self, byte: u8, time: Micros) -> Decoded<'_> {
+    // the headers are short and rejected as soon as a byte doesn't fit
+    let in_frame = matches!(
+      self.state,
+      State::Data { .. } | State::Skip { .. } | State::Tpm2End { .. }
+    );
+    if in_frame && time.saturating_sub(self.last_byte) > FRAME_TIMEOUT {
+      self.state = State::Idle;
+    }
+    self.last_byte = time;
+
+    match self.state {
+      State::Idle => {
+        self.header[0] = byte;
+        self.header_len = 1;
+        self.state = match byte {
+          b'A' => State::AdalightHeader,
+          TPM2_START => State::Tpm2Header,
+          _ => return Decoded::Rejected(&self.header[..1]),
+        };
+        Decoded::Pending
+      }
+      State::AdalightHeader => {
+        self.header[self.header_len] = byte;
+        self.header_len += 1;
+        let valid = match self.header_len {
+          2 => byte == b'd',
+          3 => byte == b'a',
+          6 => byte == self.header[3] ^ self.header[4] ^ 0x55,
+          _ => true,
+        };
+        if !valid {
+          self.state = State::Idle;
+          return Decoded::Rejected(&self.header[..self.header_len]);
+        }
+        if self.header_len == 6 {
+          let nlights = u16::from_be_bytes([self.header[3], self.header[4]]) as usize + 1;
+          if 3 * nlights > self.max_len {
+            self.state = State::Idle;
+            return Decoded::Rejected(&self.header[..6]);
+          }
+          self.start_data(3 * nlights, false);
+        }
+        Decoded::Pending
+      }
+      State::Tpm2Header => {
+        self.header[self.header_len] = byte;
+        self.header_len += 1;
+        // a stray start byte mustn't swallow the input which follows
+        if self.header_len == 2 && !matches!(byte, TPM2_DATA | TPM2_COMMAND | TPM2_RESPONSE) {
+          self.state = State::Idle;
+          return Decoded::Rejected(&self.header[..2]);
+        }
+        if self.header_len == 4 {
+          let size = u16::from_be_bytes([self.header[2], self.header[3]]) as usize;
+          if size > self.max_len {
+            self.state = State::Idle;
+            return Decoded::Rejected(&self.header[..4]);
+          }
+          if self.header[1] == TPM2_DATA {
+            self.start_data(size, true);
+          } else if size > 0 {
+            self.state = State::Skip { remaining: size };
+          } else {
+            self.state = State::Tpm2End { data: false };
+          }
+        }
+        Decoded::Pending
+      }
+      State::Data { remaining, tpm2 } => {
+        self.data.push(byte);
+        self.state = match (remaining - 1, tpm2) {
+          (0, true) => State::Tpm2End { data: true },
+          (0, false) => {
+            self.state = State::Idle;
+            return Decoded::Frame(&self.data);
+          }
+          (remaining, tpm2) => State::Data { remaining, tpm2 },
+        };
+        Decoded::Pending
+      }
+      State::Skip { remaining } => {
+        self.state = match remaining - 1 {
+          0 => State::Tpm2End { data: false },
+          remaining => State::Skip { remaining },
+        };
+        Decoded::Pending
+      }
+      State::Tpm2End { data } => {
+        self.state = State::Idle;
+        // frames without the end byte are dropped
+        if data && byte == TPM2_END {
+          Decoded::Frame(&self.data)
+        } else {
+          Decoded::Pending
+        }
+      }
+    }
+  }
+
+  fn start_data(&mut self, len: usize, tpm2: bool) {
+    self.data.clear();
+    self.state = match (len, tpm2) {
+      (0, true) => State::Tpm2End { data: true },
+      (0, false) => State::Idle,
+      (remaining, tpm2) => State::Data { remaining, tpm2 },
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec;
+
+  use super::*;
+
+  #[derive(Debug, PartialEq, Eq)]
+  enum Output {
+    Frame(Vec<u8>),
+    Rejected(Vec<u8>),
+  }
+
+  fn decode(decoder: &mut StreamDecoder, bytes: &[u8]) -> Vec<Output> {
+    let mut outputs = Vec::new();
+    for &byte in bytes {
+      match decoder.push(byte, 0) {
+        Decoded::Pending => {}
+        Decoded::Frame(rgb) => outputs.push(Output::Frame(rgb.to_vec())),
+        Decoded::Rejected(bytes) => outputs.push(Output::Rejected(bytes.to_vec())),
+      }
+    }
+    outputs
+  }
+
+  #[test]
+  fn decodes_adalight_frames() {
+    let mut decoder = StreamDecoder::new(2);
+    let frame = [b'A', b'd', b'a', 0, 1, 0x54, 1, 2, 3, 4, 5, 6];
+    assert_eq!(
+      decode(&mut decoder, &frame),
+      [Output::Frame(vec![1, 2, 3, 4, 5, 6])]
+    );
+  }
+
+  #[test]
+  fn rejects_adalight_headers_with_a_wrong_checksum() {
+    let mut decoder = StreamDecoder::new(2);
+    assert_eq!(
+      decode(&mut decoder, &[b'A', b'd', b'a', 0, 1, 0]),
+      [Output::Rejected(vec![b'A', b'd', b'a', 0, 1, 0])]
+    );
+  }
+
+  #[test]
+  fn decodes_tpm2_frames() {
+    let mut decoder = StreamDecoder::new(1);
+    let frame = [TPM2_START, TPM2_DATA, 0, 3, 1, 2, 3, TPM2_END];
+    assert_eq!(decode(&mut decoder, &frame), [Output::Frame(vec![1, 2, 3])]);
+    // frames without the end byte are dropped
+    let frame = [TPM2_START, TPM2_DATA, 0, 3, 1, 2, 3, 0];
+    assert_eq!(decode(&mut decoder, &frame), []);
+  }
+
+  #[test]
+  fn rejects_frames_larger_than_the_strip() {
+    let mut decoder = StreamDecoder::new(1);
+    let header = [b'A', b'd', b'a', 0xff, 0xff, 0x55];
+    assert_eq!(
+      decode(&mut decoder, &header),
+      [Output::Rejected(header.to_vec())]
+    );
+    let header = [TPM2_START, TPM2_DATA, 0, 6];
+    assert_eq!(
+      decode(&mut decoder, &header),
+      [Output::Rejected(header.to_vec())]
+    );
+    assert_eq!(decode(&mut decoder, b"x"), [Output::Rejected(vec![b'x'])]);
+  }
+
+  #[test]
+  fn drops_interrupted_frames() {
+    let mut decoder = StreamDecoder::new(1);
+    decode(&mut decoder, &[TPM2_START, TPM2_DATA, 0, 3, 1]);
+    assert!(matches!(
+      decoder.push(b'x', FRAME_TIMEOUT),
+      Decoded::Pending
+    ));
+    match decoder.push(b'y', 2 * FRAME_TIMEOUT + 1) {
+      Decoded::Rejected(bytes) => assert_eq!(bytes, b"y"),
+      _ => panic!("the frame should have been dropped"),
+    }
+  }
+
+  #[test]
+  fn skips_tpm2_commands() {
+    let mut decoder = StreamDecoder::new(1);
+    let command = [TPM2_START, TPM2_COMMAND, 0, 2, 9, 9, TPM2_END];
+    let frame = [TPM2_START, TPM2_DATA, 0, 3, 1, 2, 3, TPM2_END];
+    let bytes = [&command[..], &frame[..]].concat();
+    assert_eq!(decode(&mut decoder, &bytes), [Output::Frame(vec![1, 2, 3])]);
+  }
+
+  #[test]
+  fn rejects_other_input() {
+    let mut decoder = StreamDecoder::new(1);
+    assert_eq!(
+      decode(&mut decoder, b"hi"),
+      [Output::Rejected(vec![b'h']), Output::Rejected(vec![b'i'])]
+    );
+    // an unknown TPM2 type doesn't swallow the following input
+    assert_eq!(
+      decode(&mut decoder, &[TPM2_START, b'x', b'y']),
+      [
+        Output::Rejected(vec![TPM2_START, b'x']),
+        Output::Rejected(vec![b'y'])
+      ]
+    );
+  }
+}
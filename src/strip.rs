@@ -0,0 +1,112 @@
+//! Description of the physical LED strip.
+
+/// Order in which the channels of a pixel are sent to the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelOrder {
+  Rgb,
+  Rbg,
+  Grb,
+  Gbr,
+  Brg,
+  Bgr,
+  Rgbw,
+  Grbw,
+}
+
+impl ChannelOrder {
+  /// Indices into `[r, g, b, w]` in the order they are sent.
+  pub const fn channels(self) -> &'static [usize] {
+    match self {
+      ChannelOrder::Rgb => &[0, 1, 2],
+      ChannelOrder::Rbg => &[0, 2, 1],
+      ChannelOrder::Grb => &[1, 0, 2],
+      ChannelOrder::Gbr => &[1, 2, 0],
+      ChannelOrder::Brg => &[2, 0, 1],
+      ChannelOrder::Bgr => &[2, 1, 0],
+      ChannelOrder::Rgbw => &[0, 1, 2, 3],
+      ChannelOrder::Grbw => &[1, 0, 2, 3],
+    }
+  }
+
+  pub const fn has_white(self) -> bool {
+    self.channels().len() == 4
+  }
+
+  pub const fn bits_per_pixel(self) -> u32 {
+    8 * self.channels().len() as u32
+  }
+}
+
+/// Bit timing of the chips.
+/// A bit takes `t1 + t2 + t3` cycles of the state machine:
+/// high for `t1`, then high (one) or low (zero) for `t2`, and low for `t3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+  pub t1: u8,
+  pub t2: u8,
+  pub t3: u8,
+  /// Bits per second.
+  pub freq: u32,
+  /// How long the line must be held low for the strip to latch a frame, in microseconds.
+  pub reset_us: u32,
+}
+
+impl Timing {
+  pub const WS2811: Self = Self {
+    t1: 2,
+    t2: 5,
+    t3: 3,
+    freq: 400_000,
+    reset_us: 280,
+  };
+  pub const WS2812: Self = Self {
+    t1: 2,
+    t2: 5,
+    t3: 3,
+    freq: 800_000,
+    reset_us: 280,
+  };
+  pub const SK6812: Self = Self {
+    t1: 2,
+    t2: 5,
+    t3: 3,
+    freq: 800_000,
+    reset_us: 80,
+  };
+
+  pub const fn cycles_per_bit(self) -> u32 {
+    (self.t1 + self.t2 + self.t3) as u32
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripConfig {
+  pub nlights: usize,
+  pub order: ChannelOrder,
+  pub timing: Timing,
+}
+
+impl StripConfig {
+  pub const fn new(nlights: usize, order: ChannelOrder, timing: Timing) -> Self {
+    Self {
+      nlights,
+      order,
+      timing,
+    }
+  }
+
+  pub const fn ws2812b(nlights: usize) -> Self {
+    Self::new(nlights, ChannelOrder::Grb, Timing::WS2812)
+  }
+
+  pub const fn sk6812_rgbw(nlights: usize) -> Self {
+    Self::new(nlights, ChannelOrder::Grbw, Timing::SK6812)
+  }
+}
+
+impl Default for StripConfig {
+  /// Our SK6812 RGBW strip consisting of four arcs.
+  fn default() -> Self {
+    Self::sk6812_rgbw(4 * 60)
+  }
+}
@@ -0,0 +1,85 @@
+//! Writing to the flash the firmware runs from, using the functions of the boot ROM.
+//!
+//! While the flash is written it can't be read, so the code doing it runs from RAM
+//! with interrupts disabled.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Where the flash is mapped into the address space by XIP.
+const XIP_BASE: u32 = 0x1000_0000;
+pub const PAGE_SIZE: usize = 256;
+pub const SECTOR_SIZE: usize = 4096;
+/// 4K sector erase command of the flash chip.
+const SECTOR_ERASE: u8 = 0x20;
+
+/// The flash contents at `offset`, read through XIP.
+pub fn read(offset: u32, len: usize) -> &'static [u8] {
+  unsafe { core::slice::from_raw_parts((XIP_BASE + offset) as *const u8, len) }
+}
+
+/// Programs `page` at `offset`, after erasing the sector starting there if `erase`.
+/// The page has to be erased already otherwise.
+pub fn write(offset: u32, page: &[u8; PAGE_SIZE], erase: bool) {
+  // looked up beforehand, the lookup runs from flash
+  let rom = RomFunctions::lookup();
+  // XIP is set up again by running a copy of the second stage boot loader,
+  // the generic mode of the boot ROM would make reading from flash slow
+  let boot2 = unsafe { core::ptr::read_volatile(XIP_BASE as *const [u32; 64]) };
+  cortex_m::interrupt::free(|_| unsafe {
+    write_from_ram(&rom, offset, page.as_ptr(), erase, boot2.as_ptr());
+  });
+}
+
+struct RomFunctions {
+  connect_internal_flash: unsafe extern "C" fn(),
+  flash_exit_xip: unsafe extern "C" fn(),
+  flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+  flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+  flash_flush_cache: unsafe extern "C" fn(),
+}
+
+impl RomFunctions {
+  fn lookup() -> Self {
+    unsafe {
+      Self {
+        connect_internal_flash: core::mem::transmute(rom_function(b"IF")),
+        flash_exit_xip: core::mem::transmute(rom_function(b"EX")),
+        flash_range_erase: core::mem::transmute(rom_function(b"RE")),
+        flash_range_program: core::mem::transmute(rom_function(b"RP")),
+        flash_flush_cache: core::mem::transmute(rom_function(b"FC")),
+      }
+    }
+  }
+}
+
+/// Address of a function of the boot ROM, see section 2.8.3 of the RP2040 datasheet.
+unsafe fn rom_function(tag: &[u8; 2]) -> usize {
+  type LookupFn = unsafe extern "C" fn(*const u16, u32) -> usize;
+  let lookup: LookupFn =
+    core::mem::transmute(core::ptr::read_volatile(0x18 as *const u16) as usize);
+  let table = core::ptr::read_volatile(0x14 as *const u16) as *const u16;
+  lookup(table, u16::from_le_bytes(*tag) as u32)
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn write_from_ram(
+  rom: &RomFunctions,
+  offset: u32,
+  page: *const u8,
+  erase: bool,
+  boot2: *const u32,
+) {
+  compiler_fence(Ordering::SeqCst);
+  (rom.connect_internal_flash)();
+  (rom.flash_exit_xip)();
+  if erase {
+    (rom.flash_range_erase)(offset, SECTOR_SIZE, SECTOR_SIZE as u32, SECTOR_ERASE);
+  }
+  (rom.flash_range_program)(offset, page, PAGE_SIZE);
+  (rom.flash_flush_cache)();
+  // thumb code, so the lowest bit is set
+  let boot2: unsafe extern "C" fn() = core::mem::transmute(boot2 as usize + 1);
+  boot2();
+  compiler_fence(Ordering::SeqCst);
+}
@@ -1,4 +1,4 @@
-use core::cell::RefCell;
+use core::{cell::RefCell, fmt};
 
 use cortex_m::interrupt::Mutex;
 use rp_pico::{
@@ -33,6 +33,20 @@ pub fn init_uart(
   });
 }
 
+/// Writes to the UART, for code which takes any `fmt::Write`.
+/// Unlike `uprintln!` it doesn't write to the USB serial port.
+pub struct UartWriter;
+
+impl fmt::Write for UartWriter {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    cortex_m::interrupt::free(|cs| {
+      let mut uart = UART_PERIPHERAL.borrow(cs).borrow_mut();
+      let uart = uart.as_mut().expect("uart not initialized");
+      fmt::Write::write_str(uart, s)
+    })
+  }
+}
+
 #[macro_export]
 macro_rules! uprint {
   ($($arg:tt)*) => {
@@ -59,7 +73,15 @@ macro_rules! uprintln {
       let uart = uart
         .as_mut()
         .expect("uart not initialized");
-      writeln!(uart, $($arg)*).unwrap();
+      // `match` keeps the arguments alive, so they are only evaluated once
+      #[allow(clippy::match_single_binding)]
+      match format_args!($($arg)*) {
+        args => {
+          writeln!(uart, "{}", args).unwrap();
+          // the log goes to the USB serial port as well
+          let _ = writeln!($crate::util::usb::UsbWriter, "{}", args);
+        }
+      }
     })
   };
 }
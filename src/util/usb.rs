@@ -0,0 +1,53 @@
+use core::{cell::RefCell, fmt};
+
+use cortex_m::interrupt::Mutex;
+use rp_pico::hal::usb::UsbBus;
+use usb_device::{class_prelude::UsbBusAllocator, prelude::*};
+use usbd_serial::{SerialPort, USB_CLASS_CDC};
+
+pub struct Usb {
+  pub device: UsbDevice<'static, UsbBus>,
+  pub serial: SerialPort<'static, UsbBus>,
+}
+
+pub static USB: Mutex<RefCell<Option<Usb>>> = Mutex::new(RefCell::new(None));
+
+/// Exposes a CDC-ACM serial port, which works like the UART.
+pub fn init_usb(bus: &'static UsbBusAllocator<UsbBus>) {
+  let serial = SerialPort::new(bus);
+  // test VID and PID of pid.codes
+  let device = UsbDeviceBuilder::new(bus, UsbVidPid(0x1209, 0x0001))
+    .manufacturer("arcus")
+    .product("arcus LED controller")
+    .serial_number("arcus")
+    .device_class(USB_CLASS_CDC)
+    .build();
+
+  cortex_m::interrupt::free(|cs| {
+    USB.borrow(cs).replace(Some(Usb { device, serial }));
+  });
+}
+
+/// Writes to the USB serial port.
+/// Whatever doesn't fit into the buffer is dropped, so nothing blocks while no host is reading.
+pub struct UsbWriter;
+
+impl fmt::Write for UsbWriter {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    cortex_m::interrupt::free(|cs| {
+      let mut usb = USB.borrow(cs).borrow_mut();
+      let usb = match usb.as_mut() {
+        Some(usb) if usb.device.state() == UsbDeviceState::Configured => usb,
+        _ => return,
+      };
+      let mut bytes = s.as_bytes();
+      while !bytes.is_empty() {
+        match usb.serial.write(bytes) {
+          Ok(n) if n > 0 => bytes = &bytes[n..],
+          _ => break,
+        }
+      }
+    });
+    Ok(())
+  }
+}